@@ -0,0 +1,27 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::env::current_dir;
+use tui_markup_renderer::backend::backend::TestBackend;
+use tui_markup_renderer::markup_parser::MarkupParser;
+
+fn layout_pass(c: &mut Criterion) {
+    let filepath = match current_dir() {
+        Ok(exe_path) => format!(
+            "{}/samples/tui-markup-sample/assets/layout.tml",
+            exe_path.display()
+        ),
+        Err(_e) => String::new(),
+    };
+    let mut mp: MarkupParser<TestBackend> = MarkupParser::new(filepath, None, None);
+
+    // changing the size every iteration forces the layout pass
+    let mut width = 100;
+    c.bench_function("layout pass (sample layout)", |b| {
+        b.iter(|| {
+            width = if width == 100 { 101 } else { 100 };
+            black_box(mp.render_plain(width, 40))
+        })
+    });
+}
+
+criterion_group!(benches, layout_pass);
+criterion_main!(benches);
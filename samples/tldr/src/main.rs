@@ -1,7 +1,9 @@
 use crossterm::event::KeyCode::{self, Char};
 use std::{collections::HashMap, io};
 use tui::backend::CrosstermBackend;
-use tui_markup_renderer::{event_response::EventResponse, markup_parser::MarkupParser};
+use tui_markup_renderer::{
+    event_response::EventResponse, markup_parser::MarkupParser, state_value::StateValue,
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // get access to StdOut
@@ -9,20 +11,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Get the backend for TUI
     let backend = CrosstermBackend::new(stdout);
     // prepare the internal state for the app info
-    let state = Some(HashMap::new());
+    let state: Option<HashMap<String, StateValue>> = Some(HashMap::new());
 
     // prepare the markup parser
     let mut mp = MarkupParser::new("./assets/layout.tml".to_string(), None, state);
 
     // Dialogs generate button identifiers following the convention "on_<dialog id>_btn_<button name>"
-    mp.add_action("open_dialog", |state| {
+    mp.add_action("open_dialog", |state, _node| {
         let mut state = state.clone();
-        state.insert("show_dialog".to_string(), "true".to_string());
+        state.insert("show_dialog".to_string(), true.into());
         EventResponse::STATE(state)
     })
-    .add_action("on_dlg_btn_Okay", |state| {
+    .add_action("on_dlg_btn_Okay", |state, _node| {
         let mut state = state.clone();
-        state.insert("show_dialog".to_string(), "false".to_string());
+        state.insert("show_dialog".to_string(), false.into());
         EventResponse::STATE(state)
     })
     .ui_loop(backend, |key_event, mut state| {
@@ -39,11 +41,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         match pressed {
             "close_dialog" => {
-                state.insert("show_dialog".to_string(), "false".to_string());
+                state.insert("show_dialog".to_string(), false.into());
                 EventResponse::STATE(state)
             }
             "close" => {
-                state.insert("show_dialog".to_string(), "false".to_string());
+                state.insert("show_dialog".to_string(), false.into());
                 EventResponse::QUIT
             }
             _ => EventResponse::NOOP,
@@ -2,7 +2,9 @@ use clap::Parser;
 use crossterm::event::KeyCode::{self, Char};
 use std::{collections::HashMap, io};
 use tui::backend::CrosstermBackend;
-use tui_markup_renderer::{event_response::EventResponse, markup_parser::MarkupParser};
+use tui_markup_renderer::{
+    event_response::EventResponse, markup_parser::MarkupParser, state_value::StateValue,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -24,11 +26,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
-    let state = Some(HashMap::new());
+    let state: Option<HashMap<String, StateValue>> = Some(HashMap::new());
     /*
     let state = Some(HashMap::from([(
         "tabs-cmp:index".to_string(),
-        "tab2".to_string(),
+        "tab2".into(),
     )]));
     */
 
@@ -36,26 +38,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     mp.add_action("do_something", |state, _node| {
         let mut state = state.clone();
         let key = "showMessageDialog".to_string();
-        state.insert(key, "true".to_string());
+        state.insert(key, true.into());
         EventResponse::STATE(state.clone())
     })
     .add_action("do_something_else", |state, _node| {
         let mut state = state.clone();
         let key = "showMessageDialog".to_string();
-        state.insert(key, "true".to_string());
+        state.insert(key, true.into());
         EventResponse::STATE(state.clone())
     })
     .add_action("on_dlg1_btn_Yes", |_state, _node| EventResponse::QUIT)
     .add_action("on_close_dialog", |state, _node| {
         let mut state = state.clone();
         let key = "showMessageDialog".to_string();
-        state.insert(key, "false".to_string());
+        state.insert(key, false.into());
         EventResponse::STATE(state.clone())
     })
     .add_action("on_dlg1_btn_Cancel", |state, _node| {
         let mut state = state.clone();
         let key = "showQuitDialog".to_string();
-        state.insert(key, "false".to_string());
+        state.insert(key, false.into());
         EventResponse::STATE(state.clone())
     });
 
@@ -84,14 +86,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
 
             if pressed == '\r' {
-                let new_value = "false";
-                new_state.insert(key, new_value.to_string());
+                let new_value = false;
+                new_state.insert(key, new_value.into());
                 return EventResponse::STATE(new_state);
             }
 
             if pressed == 'q' {
-                let new_value = "true";
-                new_state.insert(key, new_value.to_string());
+                let new_value = true;
+                new_state.insert(key, new_value.into());
                 return EventResponse::STATE(new_state);
             }
 
@@ -102,6 +104,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         })
     } else {
         env_logger::init();
-        mp.test_check(backend)
+        mp.test_check(backend, true).map(|_| ())
     }
 }
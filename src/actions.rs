@@ -6,21 +6,22 @@ use std::{
     fmt,
     ops::Deref,
 };
-use tui::layout::Rect;
-use tui::{
+
+use crate::backend::layout::Rect;
+use crate::backend::{
     backend::{Backend, CrosstermBackend, TestBackend},
     Frame,
 };
-
 use crate::event_response::EventResponse;
 use crate::markup_element::MarkupElement;
+use crate::state_value::StateValue;
 
-type Callback = fn(HashMap<String, String>, Option<MarkupElement>) -> EventResponse;
+type Callback = fn(HashMap<String, StateValue>, Option<MarkupElement>) -> EventResponse;
 
 pub trait IActionsStorage {
     fn has_action(&self, name: String) -> bool;
     fn add_action(&mut self, name: String, render: Callback) -> &mut Self;
-    fn execute(&self, name: String, state: HashMap<String, String>, node: Option<MarkupElement>) -> Option<EventResponse>;
+    fn execute(&self, name: String, state: HashMap<String, StateValue>, node: Option<MarkupElement>) -> Option<EventResponse>;
 }
 
 #[derive(Default)]
@@ -38,7 +39,7 @@ impl ActionsStorage {
 
 impl IActionsStorage for ActionsStorage {
     fn add_action(&mut self, name: String, action: Callback) -> &mut Self {
-        self.storage.entry(name).or_insert(action);
+        self.storage.insert(name, action);
         self
     }
 
@@ -46,7 +47,7 @@ impl IActionsStorage for ActionsStorage {
         self.storage.contains_key(&name)
     }
 
-    fn execute(&self, name: String, state: HashMap<String, String>, node: Option<MarkupElement>) -> Option<EventResponse> {
+    fn execute(&self, name: String, state: HashMap<String, StateValue>, node: Option<MarkupElement>) -> Option<EventResponse> {
         let opt = self.storage.get(&name);
         opt.map(|f| f(state, node.clone()))
     }
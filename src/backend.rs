@@ -0,0 +1,13 @@
+//! The terminal UI library the renderer is built on. `tui` is used by
+//! default, enable the `ratatui` feature (and disable the default ones) to
+//! build on top of `ratatui` instead. Use this module to name the widgets,
+//! backends and frames passed to the parser so the versions always match.
+
+#[cfg(all(not(feature = "tui"), not(feature = "ratatui")))]
+compile_error!("enable either the `tui` (default) or the `ratatui` feature");
+
+#[cfg(feature = "ratatui")]
+pub use ratatui::*;
+
+#[cfg(all(feature = "tui", not(feature = "ratatui")))]
+pub use tui::*;
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
+use crate::state_value::StateValue;
+
 pub enum EventResponse {
     NOOP,
     QUIT,
-    STATE(HashMap<String, String>),
-    CLEANFOCUS(HashMap<String, String>),
+    STATE(HashMap<String, StateValue>),
+    CLEANFOCUS(HashMap<String, StateValue>),
+    /// Applies the state and runs the named action after the next render.
+    THEN(HashMap<String, StateValue>, String),
 }
 
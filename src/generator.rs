@@ -1,14 +1,56 @@
 use clap::Parser;
 use std::fs::File;
-use std::io::{Write, Error};
+use std::io::{Write, Error, ErrorKind};
 use std::process::{Command, self};
 use std::str::FromStr;
 use std::{fs::create_dir, path::Path};
 
 #[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
 enum ProjectType {
     SIMPLE,
     SHELL,
+    FORM,
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+enum BackendType {
+    TUI,
+    RATATUI,
+}
+
+impl BackendType {
+    /// Crate providing the widgets and the terminal backends.
+    fn crate_name(&self) -> &str {
+        match self {
+            BackendType::TUI => "tui",
+            BackendType::RATATUI => "ratatui",
+        }
+    }
+
+    /// Dependency added to the generated project (`cargo add` syntax).
+    fn dependency(&self) -> &str {
+        match self {
+            BackendType::TUI => "tui@0.19.0",
+            BackendType::RATATUI => "ratatui@0.20.1",
+        }
+    }
+}
+
+impl FromStr for BackendType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tui" => Ok(BackendType::TUI),
+            "ratatui" => Ok(BackendType::RATATUI),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown backend \"{}\" (expected tui or ratatui)", s),
+            )),
+        }
+    }
 }
 
 impl FromStr for ProjectType {
@@ -18,6 +60,7 @@ impl FromStr for ProjectType {
         match s.to_ascii_lowercase().as_str() {
             "simple" => Ok(ProjectType::SIMPLE),
             "shell" => Ok(ProjectType::SHELL),
+            "form" => Ok(ProjectType::FORM),
             _ => Ok(ProjectType::SIMPLE),
         }
     }
@@ -27,7 +70,7 @@ impl FromStr for ProjectType {
 #[derive(Parser, Debug)]
 #[command(name = "tui markup generator")]
 #[command(author = "Oscar Andrés Granada <oscar.andres.granadab@gmail.com>")]
-#[command(version = "1.0")]
+#[command(version)]
 #[command(about = "Create your TUI Markup based apps simplily", long_about = None)]
 struct Args {
     /// Project name
@@ -38,17 +81,21 @@ struct Args {
     #[arg(short = 'o', long)]
     output_path: String,
 
-    /// Project type
+    /// Project type (simple, shell or form)
     #[arg(short = 't', long)]
     project_type: ProjectType,
+
+    /// TUI library used by the project (tui or ratatui)
+    #[arg(short = 'b', long, default_value = "tui")]
+    backend: BackendType,
 }
 
 fn draw_header() {
-    println!("");
+    println!();
     println!("╭──────────────────────────╮");
     println!("│   TUI Markup Generator   │");
     println!("╰──────────────────────────╯");
-    println!("");
+    println!();
 }
 
 fn mkdir(path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
@@ -58,11 +105,13 @@ fn mkdir(path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn write_main_file(path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+fn write_main_file(
+    backend: &BackendType,
+    path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
     if let Some(target_path) = path {
         let mut file = File::create(target_path)?;
-        file.write(
-            b"use crossterm::event::KeyCode::Char;
+        let content = "use crossterm::event::KeyCode::Char;
 use std::{collections::HashMap, io};
 use tui::backend::CrosstermBackend;
 use tui_markup_renderer::{event_response::EventResponse, markup_parser::MarkupParser};
@@ -80,8 +129,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Dialogs generate button identifiers following the convention \"on_<dialog id>_btn_<button name>\"
     mp.add_action(\"do_something\", |state, _node| {
-        let mut state = state.clone();
-        state.insert(\"new_value\".to_string(), \"true\".to_string());
+        let mut state = state;
+        state.insert(\"new_value\".to_string(), true.into());
         EventResponse::STATE(state)
     })
     .ui_loop(backend, |key_event, _state| {
@@ -100,8 +149,66 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             _ => EventResponse::NOOP,
         }
     })
-}"
-        )?;
+}";
+        let content = content.replace(
+            "use tui::backend",
+            format!("use {}::backend", backend.crate_name()).as_str(),
+        );
+        file.write_all(content.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_form_main_file(
+    backend: &BackendType,
+    path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(target_path) = path {
+        let mut file = File::create(target_path)?;
+        let content = "use crossterm::event::KeyCode::Char;
+use std::{collections::HashMap, io};
+use tui::backend::CrosstermBackend;
+use tui_markup_renderer::{event_response::EventResponse, markup_parser::MarkupParser};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // get access to StdOut
+    let stdout = io::stdout();
+    // Get the backend for TUI
+    let backend = CrosstermBackend::new(stdout);
+    // prepare the internal state for the app info
+    let state = Some(HashMap::new());
+
+    // prepare the markup parser
+    let mut mp = MarkupParser::new(\"./assets/main.xml\".to_string(), None, state);
+
+    // The radio groups store the selected value in the state key named by their \"bind\" property
+    mp.add_action(\"submit_form\", |state, _node| {
+        let mut state = state;
+        let size = state.get(\"form.size\").map(|value| value.to_string());
+        let color = state.get(\"form.color\").map(|value| value.to_string());
+        let summary = format!(
+            \"size: {}, color: {}\",
+            size.unwrap_or_default(),
+            color.unwrap_or_default()
+        );
+        state.insert(\"form.summary\".to_string(), summary.into());
+        EventResponse::STATE(state)
+    })
+    .ui_loop(backend, |key_event, _state| match key_event.code {
+        Char('q') => EventResponse::QUIT,
+        _ => EventResponse::NOOP,
+    })?;
+
+    if let Some(summary) = mp.get_state(\"form.summary\") {
+        println!(\"Submitted {}\", summary);
+    }
+    Ok(())
+}";
+        let content = content.replace(
+            "use tui::backend",
+            format!("use {}::backend", backend.crate_name()).as_str(),
+        );
+        file.write_all(content.as_bytes())?;
     }
     Ok(())
 }
@@ -225,6 +332,37 @@ fn write_simple_layout_file(
     Ok(())
 }
 
+fn write_form_layout_file(
+    project_name: String,
+    path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(target_path) = path {
+        let mut file = File::create(target_path)?;
+        file.write_fmt(format_args!(
+            "<layout id=\"root\" direction=\"vertical\">
+  <p id=\"header\" constraint=\"3\">
+    {}: choose the options and press Submit, Q quits.
+  </p>
+  <radio-group id=\"size\" index=\"1\" bind=\"form.size\" title=\" Size \" border=\"all\" constraint=\"7\">
+    <radio value=\"small\">Small</radio>
+    <radio value=\"medium\">Medium</radio>
+    <radio value=\"large\">Large</radio>
+  </radio-group>
+  <radio-group id=\"color\" index=\"2\" bind=\"form.color\" title=\" Color \" border=\"all\" constraint=\"7\">
+    <radio value=\"red\">Red</radio>
+    <radio value=\"green\">Green</radio>
+    <radio value=\"blue\">Blue</radio>
+  </radio-group>
+  <button id=\"btn_submit\" index=\"3\" action=\"submit_form\" constraint=\"5\"> Submit </button>
+  <block id=\"summary\" title=\" {{{{form.summary}}}} \" border=\"all\" constraint=\"3\">
+  </block>
+</layout>",
+            project_name,
+        ))?;
+    }
+    Ok(())
+}
+
 fn run<'a>(cmd: &'a str, args: &[&'a str], curr_dir: Option<String>) -> (bool, String, String) {
     let cd = curr_dir.unwrap_or(".".to_string());
     let res = Command::new(cmd)
@@ -234,9 +372,9 @@ fn run<'a>(cmd: &'a str, args: &[&'a str], curr_dir: Option<String>) -> (bool, S
         .expect("failed to execute command");
     if res.status.success() {
         let out = String::from_utf8(res.stdout);
-        let out = out.unwrap_or(String::default());
+        let out = out.unwrap_or_default();
         let err = String::from_utf8(res.stderr);
-        let err = err.unwrap_or(String::default());
+        let err = err.unwrap_or_default();
         (true, out.clone(), err.clone())
     } else {
         (false, String::default(), String::default())
@@ -250,6 +388,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         output_path,
         project_name,
         project_type,
+        backend,
     } = args;
 
     draw_header();
@@ -267,23 +406,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     if let Some(target_path) = path_opt {
         let (success, _out, err) = run(
             "cargo",
-            &["new", "--name", &project_name.as_str(), target_path],
+            &["new", "--name", project_name.as_str(), target_path],
             None,
         );
         if success {
             println!("\t✓ {}", err.trim());
             println!(" • Installing dependencies.");
+            let renderer = format!("tui-markup-renderer@{}", env!("CARGO_PKG_VERSION"));
+            let mut renderer_args = vec!["add", renderer.as_str()];
+            if let BackendType::RATATUI = backend {
+                renderer_args.extend(["--no-default-features", "--features", "ratatui"]);
+            }
+            let (renderer_success, _, _) =
+                run("cargo", &renderer_args, Some(target_path.to_string()));
             let (deps_success, _, _) = run(
                 "cargo",
-                &[
-                    "add",
-                    "tui-markup-renderer@1.1.0",
-                    "tui@0.19.0",
-                    "crossterm@0.25.0",
-                ],
+                &["add", backend.dependency(), "crossterm@0.25.0"],
                 Some(target_path.to_string()),
             );
-            if deps_success {
+            if renderer_success && deps_success {
                 run("cargo", &["build"], Some(target_path.to_string()));
                 println!("\t✓ Dependencies installed.");
                 println!(" • Creating UI files.");
@@ -291,8 +432,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 match project_type {
                     ProjectType::SIMPLE => write_simple_layout_file(project_name, path_lyt)?,
                     ProjectType::SHELL => write_shell_layout_file(project_name, path_lyt)?,
+                    ProjectType::FORM => write_form_layout_file(project_name, path_lyt)?,
+                };
+                match project_type {
+                    ProjectType::FORM => write_form_main_file(&backend, path_main)?,
+                    _ => write_main_file(&backend, path_main)?,
                 };
-                write_main_file(path_main)?;
                 println!("\t✓ Files created.");
                 println!("\nProject location: {}", target_path);
             }
@@ -1,7 +1,12 @@
+pub mod backend;
 pub mod event_response;
 pub mod markup_element;
+pub mod markup_error;
 pub mod markup_parser;
+pub mod markup_parser_builder;
+pub mod state_value;
 pub mod storage;
 pub mod actions;
 pub mod styles;
+pub mod terminal_guard;
 pub mod utils;
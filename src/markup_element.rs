@@ -1,6 +1,11 @@
 use std::{fmt, cell::RefCell, collections::HashMap, rc::Rc};
 
+/// With the `serde` feature the tree can be (de)serialized; `deep`,
+/// `parent_node` and `dependencies` are skipped, use `rebuild_parents` after
+/// deserializing.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MarkupElement {
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub deep: usize,
     pub id: String,
     pub name: String,
@@ -8,10 +13,127 @@ pub struct MarkupElement {
     pub text: Option<String>,
     pub attributes: HashMap<String, String>,
     pub children: Vec<Rc<RefCell<MarkupElement>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub parent_node: Option<Rc<RefCell<MarkupElement>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub dependencies: Vec<String>,
 }
 
+impl MarkupElement {
+    /// Rebuilds `deep` and the `parent_node` links of the descendants. The
+    /// direct children can only point to this element when it is shared, use
+    /// `rebuild_parents_of` for a root wrapped in `Rc<RefCell<_>>`.
+    pub fn rebuild_parents(&mut self) {
+        for child in self.children.iter() {
+            MarkupElement::link_children(child, self.deep + 1);
+        }
+    }
+
+    /// Same as `rebuild_parents` but also links the direct children to `root`.
+    pub fn rebuild_parents_of(root: &Rc<RefCell<MarkupElement>>) {
+        for child in root.as_ref().borrow().children.iter() {
+            child.as_ref().borrow_mut().parent_node = Some(root.clone());
+        }
+        root.as_ref().borrow_mut().rebuild_parents();
+    }
+
+    /// The `param-*` attributes without their prefix, e.g.
+    /// `param-key="darkMode"` gives `("key", "darkMode")`. Actions receive the
+    /// element that triggered them, so one action can serve many elements.
+    pub fn params(&self) -> HashMap<String, String> {
+        self.attributes
+            .iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix("param-")
+                    .map(|param| (param.to_string(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// A copy of the parent element, `None` for the root.
+    pub fn parent(&self) -> Option<MarkupElement> {
+        self.parent_node
+            .as_ref()
+            .map(|parent| parent.as_ref().borrow().clone())
+    }
+
+    /// Copies of the parent, the grandparent and so on up to the root.
+    pub fn ancestors(&self) -> Vec<MarkupElement> {
+        let mut ancestors = vec![];
+        let mut parent = self.parent();
+        while let Some(node) = parent {
+            parent = node.parent();
+            ancestors.push(node);
+        }
+        ancestors
+    }
+
+    /// Copies of the other children of the parent element, in order. The
+    /// element is skipped by identity, identifiers can repeat: a copy (e.g.
+    /// from `find_element`) is matched by its contents instead.
+    pub fn siblings(&self) -> Vec<MarkupElement> {
+        let parent = match self.parent_node.as_ref() {
+            Some(parent) => parent.as_ref().borrow(),
+            None => return vec![],
+        };
+        let position = parent
+            .children
+            .iter()
+            .position(|child| std::ptr::eq(child.as_ptr(), self))
+            .or_else(|| {
+                parent
+                    .children
+                    .iter()
+                    .position(|child| self.is_copy_of(&child.as_ref().borrow()))
+            });
+        parent
+            .children
+            .iter()
+            .enumerate()
+            .filter(|(child_position, _)| Some(*child_position) != position)
+            .map(|(_, child)| child.as_ref().borrow().clone())
+            .collect()
+    }
+
+    /// Checks if the element is a copy of `other`: same element, attributes,
+    /// text and children, the `id` field aside.
+    fn is_copy_of(&self, other: &MarkupElement) -> bool {
+        self.name == other.name
+            && self.order == other.order
+            && self.text == other.text
+            && self.attributes == other.attributes
+            && self.children.len() == other.children.len()
+            && self
+                .children
+                .iter()
+                .zip(other.children.iter())
+                .all(|(child, other_child)| Rc::ptr_eq(child, other_child))
+    }
+
+    /// A copy of the descendant with the given identifier, children are
+    /// checked before their own children.
+    pub fn child_by_id(&self, id: &str) -> Option<MarkupElement> {
+        let children: Vec<MarkupElement> = self
+            .children
+            .iter()
+            .map(|child| child.as_ref().borrow().clone())
+            .collect();
+        if let Some(child) = children.iter().find(|child| child.id == id) {
+            return Some(child.clone());
+        }
+        children.iter().find_map(|child| child.child_by_id(id))
+    }
+
+    fn link_children(node: &Rc<RefCell<MarkupElement>>, deep: usize) {
+        let mut elm = node.as_ref().borrow_mut();
+        elm.deep = deep;
+        for child in elm.children.iter() {
+            child.as_ref().borrow_mut().parent_node = Some(node.clone());
+            MarkupElement::link_children(child, deep + 1);
+        }
+    }
+}
+
 impl Clone for MarkupElement {
     fn clone(&self) -> Self {
         MarkupElement {
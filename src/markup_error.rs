@@ -0,0 +1,38 @@
+use std::{error::Error, fmt};
+
+/// Errors found while loading a markup file (or its saved state) or rendering it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupError {
+    /// Malformed markup, `line` and `column` start at 1.
+    Parse {
+        line: u64,
+        column: u64,
+        message: String,
+    },
+    /// The markup can't be loaded (unreadable files, include cycles...).
+    Load(String),
+    /// Unknown element or attribute names found in strict mode.
+    Strict(Vec<String>),
+    /// The UI state can't be saved or restored.
+    State(String),
+    /// The UI can't be rendered (e.g. the parser has no root element).
+    Render(String),
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkupError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, col {}: {}", line, column, message),
+            MarkupError::Load(message) => write!(f, "{}", message),
+            MarkupError::Strict(warnings) => write!(f, "{}", warnings.join("; ")),
+            MarkupError::State(message) => write!(f, "{}", message),
+            MarkupError::Render(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for MarkupError {}
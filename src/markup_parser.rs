@@ -1,55 +1,191 @@
-use crossterm::{
-    event::{self, Event as CEvent, KeyCode, KeyEvent},
-    terminal::{disable_raw_mode, enable_raw_mode},
+use crossterm::event::{
+    self, Event as CEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent,
+    MouseEventKind,
 };
 use log::{info, warn};
 #[allow(unused_imports)]
 use std::borrow::Borrow;
 use std::{
+    cell::RefCell,
     collections::HashMap,
     fmt,
     fs::File,
     io::BufReader,
     panic,
-    path::Path,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::mpsc,
     thread,
     time::{Duration, Instant},
     vec::Vec,
-    {borrow::BorrowMut, cell::RefCell},
 };
-use tui::{
-    backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
-    Frame, Terminal,
+use unicode_width::UnicodeWidthStr;
+use xml::{
+    common::Position,
+    reader::{EventReader, XmlEvent},
 };
-use xml::reader::{EventReader, XmlEvent};
 
 use crate::{
     actions::{ActionsStorage, IActionsStorage},
+    backend::{
+        backend::{Backend, TestBackend},
+        layout::{Alignment, Constraint, Direction, Layout, Rect},
+        style::{Color, Modifier, Style},
+        text::{Span, Spans},
+        widgets::{BarChart, Block, BorderType, Borders, Clear, Gauge, Paragraph, Sparkline, Wrap},
+        Frame, Terminal,
+    },
     event_response::EventResponse,
     markup_element::MarkupElement,
+    markup_error::MarkupError,
+    markup_parser_builder::MarkupParserBuilder,
+    state_value::StateValue,
     storage::{IRendererStorage, RendererStorage},
     styles::{IStylesStorage, StylesStorage},
-    utils::{color_from_str, extract_attribute, modifier_from_str, modifiers_from_str},
+    terminal_guard::TerminalGuard,
+    utils::{
+        buffer_to_ansi, buffer_to_plain, clip_rect, color_from_str, extract_attribute,
+        get_border_type, key_combo_from_event, labeled_values_from_str, modifier_from_str,
+        modifiers_from_str, numbers_from_str, parse_key_combo, text_height, truncate_with_ellipsis,
+        wrap_text, KeyCombo,
+    },
 };
 
 ////////////// END LIBS //////////////
 
-type ActionCallback = fn(HashMap<String, String>, Option<MarkupElement>) -> EventResponse;
+type ActionCallback = fn(HashMap<String, StateValue>, Option<MarkupElement>) -> EventResponse;
+type KeyCallback = Box<dyn FnMut(KeyEvent, &mut HashMap<String, StateValue>) -> EventResponse>;
+type FocusCallback = Box<dyn FnMut(Option<&str>, Option<&str>, &mut HashMap<String, StateValue>)>;
+type TickCallback<B> = Box<dyn FnMut(&mut MarkupParser<B>)>;
+/// An element found by `test_check`: its area, name and identifier.
+pub type CheckedElement = (Rect, String, String);
 
 pub enum Event<I> {
     Input(I),
     Tick,
 }
 
-const WIDGET_NAMES: &[&str] = &["p", "button"];
+const WIDGET_NAMES: &[&str] = &[
+    "p",
+    "menu",
+    "radio-group",
+    "button",
+    "skeleton",
+    "spinner",
+    "gauge",
+    "progress",
+    "sparkline",
+    "barchart",
+    "divider",
+];
+const ARROW_CONSUMER_NAMES: &[&str] = &["input", "list"];
+const SPACE_ACTIVATED_NAMES: &[&str] = &["button", "menu", "menu-item"];
+const INLINE_NAMES: &[&str] = &["b", "i", "u", "c"];
+const DIRECTION_NAMES: &[&str] = &["vertical", "v", "horizontal", "h"];
+/// Rounds of `THEN` actions run between two ticks, a longer chain waits for
+/// the next tick so the keys are still read.
+const MAX_CHAINED_ACTIONS: usize = 8;
+/// Smallest frame drawn when the root element doesn't set `min-width` and
+/// `min-height`: a bordered element with a few characters inside.
+const MIN_FRAME_WIDTH: u16 = 5;
+const MIN_FRAME_HEIGHT: u16 = 3;
+const ELEMENT_NAMES: &[&str] = &[
+    "layout",
+    "container",
+    "block",
+    "styles",
+    "repeat",
+    "tabs",
+    "tabs-header",
+    "tabs-body",
+    "tabs-borders",
+    "tab-item",
+    "tab-content",
+    "dialog",
+    "p",
+    "button",
+    "skeleton",
+    "spinner",
+    "gauge",
+    "progress",
+    "sparkline",
+    "barchart",
+    "divider",
+    "radio-group",
+    "radio",
+    "menu",
+    "menu-item",
+    "keybindings",
+    "bind",
+    "b",
+    "i",
+    "u",
+    "c",
+];
+const ATTRIBUTE_NAMES: &[&str] = &[
+    "id",
+    "index",
+    "action",
+    "actions",
+    "active_styles",
+    "align",
+    "as",
+    "bar-gap",
+    "bar-width",
+    "bg",
+    "bind",
+    "border",
+    "border-focus",
+    "border-focus-type",
+    "border-style",
+    "border-type",
+    "buttons",
+    "clear",
+    "constraint",
+    "constraints",
+    "default",
+    "dialog-show",
+    "direction",
+    "disabled",
+    "fg",
+    "focus_styles",
+    "footer",
+    "for",
+    "header-height",
+    "header-width",
+    "height",
+    "if",
+    "if-not",
+    "key",
+    "label",
+    "lines",
+    "max",
+    "max-height",
+    "max-width",
+    "min-height",
+    "min-width",
+    "normalize",
+    "open",
+    "orientation",
+    "overflow",
+    "preserve-whitespace",
+    "scrollable",
+    "scrollbar",
+    "separator",
+    "show",
+    "span",
+    "spinner-style",
+    "src",
+    "styles",
+    "tabs-id",
+    "title",
+    "truncate",
+    "valign",
+    "value",
+    "width",
+];
 
-/**
+/*
  * To use specific features you can use the macro:
  *   - #[cfg(feature = "test")]
  * also you can negate something:
@@ -64,15 +200,35 @@ pub struct MarkupParser<B: Backend> {
     pub path: String,
     pub failed: bool,
     pub error: Option<String>,
+    pub markup_error: Option<MarkupError>,
     pub root: Option<Rc<RefCell<MarkupElement>>>,
     pub storage: Option<Rc<RefCell<RendererStorage<B>>>>,
     pub current: i32,
     pub indexed_elements: Vec<MarkupElement>,
     pub contexts: Vec<(String, Vec<MarkupElement>)>,
-    pub state: HashMap<String, String>,
+    pub state: HashMap<String, StateValue>,
     pub actions: ActionsStorage,
     pub global_styles: StylesStorage,
     fingerprint: String,
+    unhandled_key_callback: Option<KeyCallback>,
+    focus_change_callback: Option<FocusCallback>,
+    tick_callback: Option<TickCallback<B>>,
+    ticks: u64,
+    animated: bool,
+    visible_ids: Vec<String>,
+    styles_cache: RefCell<HashMap<String, Style>>,
+    layout_fingerprint: String,
+    layout_cache: Vec<(Rect, MarkupElement)>,
+    layout_state_keys: Vec<String>,
+    warnings: Vec<String>,
+    layout_warnings: RefCell<Vec<String>>,
+    strict: bool,
+    keybindings: HashMap<KeyCombo, String>,
+    tick_rate: Duration,
+    key_repeat: bool,
+    alternate_screen: bool,
+    pending_actions: Vec<String>,
+    chained_actions: usize,
 }
 
 impl<B: Backend> fmt::Debug for MarkupParser<B> {
@@ -85,19 +241,23 @@ impl<B: Backend> fmt::Debug for MarkupParser<B> {
 }
 
 impl<B: Backend> MarkupParser<B> {
+    /// Builder to configure a parser with named options, see
+    /// `MarkupParserBuilder`.
+    pub fn builder() -> MarkupParserBuilder<B> {
+        MarkupParserBuilder::new()
+    }
+
     // Constructor
     pub fn new(
         path: String,
         optional_storage: Option<RendererStorage<B>>,
-        initial_state: Option<HashMap<String, String>>,
+        initial_state: Option<HashMap<String, StateValue>>,
     ) -> MarkupParser<B> {
         // env_logger::init();
         if !Path::new(&path).exists() {
             panic!("Markup file does not exist at {}", &path);
         }
-        let file = File::open(&path).unwrap();
-        let buffer = BufReader::new(file);
-        let parser = EventReader::new(buffer);
+        let parser = MarkupParser::<B>::read_events(Path::new(&path), &mut vec![]);
         let storage = optional_storage.unwrap_or(RendererStorage::new());
         let mut root_node: Option<Rc<RefCell<MarkupElement>>> = None;
         let mut current_node: Option<Rc<RefCell<MarkupElement>>> = None;
@@ -107,6 +267,12 @@ impl<B: Backend> MarkupParser<B> {
         let mut cntr = 0;
         let mut parent_count = 0;
         let mut actions = ActionsStorage::new();
+        let mut layout_state_keys = vec![];
+        let mut open_dialogs = vec![];
+        let mut warnings = vec![];
+        let mut keybindings = HashMap::new();
+        // text read since the last tag, it goes before the first inline child
+        let mut last_text = String::new();
         for e in parser {
             cntr += 1;
             match e {
@@ -118,6 +284,9 @@ impl<B: Backend> MarkupParser<B> {
                     for attr in attributes {
                         attrs.insert(attr.name.local_name, attr.value);
                     }
+                    let unknown_names =
+                        MarkupParser::<B>::check_names(&valid_name, &attrs, &storage);
+                    warnings.extend(unknown_names);
 
                     // TO DO: prepare default attributes depending on the node type
                     if valid_name.eq("tab-item") {
@@ -129,17 +298,71 @@ impl<B: Backend> MarkupParser<B> {
                         }
                         if !attrs.contains_key("tabs-id") && parent_node.is_some() {
                             let pn = MarkupParser::<B>::get_element(parent_node.clone());
-                            let gpn = MarkupParser::<B>::get_element(pn.parent_node);
-                            attrs.insert("tabs-id".to_string(), gpn.id);
+                            if let Some(gpn) = pn.parent() {
+                                attrs.insert("tabs-id".to_string(), gpn.id);
+                            }
+                        }
+                    }
+                    if valid_name.eq("bind") {
+                        let key = extract_attribute(&attrs, "key");
+                        match parse_key_combo(&key) {
+                            Some(combo) => {
+                                keybindings.insert(combo, extract_attribute(&attrs, "action"));
+                            }
+                            None => warnings.push(format!("Unknown key binding \"{}\"", key)),
+                        }
+                    }
+                    if valid_name.eq("repeat") {
+                        layout_state_keys.push(extract_attribute(&attrs, "for"));
+                    }
+                    for key in ["if", "if-not"] {
+                        if let Some(flag) = attrs.get(key) {
+                            layout_state_keys.push(flag.clone());
+                        }
+                    }
+                    if valid_name.eq("dialog") && extract_attribute(&attrs, "open").eq("true") {
+                        let show_flag = extract_attribute(&attrs, "show");
+                        if show_flag.is_empty() {
+                            warnings.push(format!(
+                                "Dialog #{} has open=\"true\" but no show attribute",
+                                extract_attribute(&attrs, "id")
+                            ));
+                        } else {
+                            open_dialogs.push(show_flag);
                         }
                     }
+                    // a menu opens like a dialog, its items close it
+                    if valid_name.eq("menu") {
+                        let menu_id = attrs.get("id").cloned().unwrap_or_default();
+                        let show_flag = attrs
+                            .entry("show".to_string())
+                            .or_insert(format!("{}:open", menu_id))
+                            .clone();
+                        attrs
+                            .entry("action".to_string())
+                            .or_insert("__open_menu".to_string());
+                        layout_state_keys.push(show_flag);
+                    }
+                    if valid_name.eq("menu-item") && parent_node.is_some() {
+                        let pn = MarkupParser::<B>::get_element(parent_node.clone());
+                        let show_flag = extract_attribute(&pn.attributes, "show");
+                        let action = extract_attribute(&attrs, "action");
+                        let action = if action.is_empty() {
+                            "__close_dialog".to_string()
+                        } else {
+                            format!("{};__close_dialog", action)
+                        };
+                        attrs.insert("action".to_string(), action);
+                        attrs.insert("dialog-show".to_string(), show_flag);
+                    }
                     if valid_name.eq("tab-content")
                         && !attrs.contains_key("tabs-id")
                         && parent_node.is_some()
                     {
                         let pn = MarkupParser::<B>::get_element(parent_node.clone());
-                        let gpn = MarkupParser::<B>::get_element(pn.parent_node);
-                        attrs.insert("tabs-id".to_string(), gpn.id);
+                        if let Some(gpn) = pn.parent() {
+                            attrs.insert("tabs-id".to_string(), gpn.id);
+                        }
                     }
 
                     let unknown_id = format!("unknown_elm_{}", cntr);
@@ -187,13 +410,21 @@ impl<B: Backend> MarkupParser<B> {
                     if parent_node.is_some() {
                         let parent = parent_node.clone();
                         let parent = parent.unwrap();
-                        let parent = parent.as_ref();
-                        let mut parent = parent.borrow_mut();
+                        let mut parent_elm = parent.as_ref().borrow_mut();
+                        if INLINE_NAMES.contains(&partial.name.as_str())
+                            && !MarkupParser::<B>::has_inline_children(&parent_elm)
+                            && !last_text.is_empty()
+                        {
+                            let text =
+                                MarkupParser::<B>::text_node(&last_text, &parent, partial.deep);
+                            parent_elm.children.push(text);
+                        }
                         let son = current_node.clone().unwrap();
-                        parent.children.push(son);
+                        parent_elm.children.push(son);
                     }
+                    last_text.clear();
 
-                    if elm_idx != -1 {
+                    if MarkupParser::<B>::is_focusable(&partial) {
                         indexed_elements.push(partial);
                     }
 
@@ -201,26 +432,89 @@ impl<B: Backend> MarkupParser<B> {
                     parent_count = elm_idx;
                 }
                 Ok(XmlEvent::Characters(ref r)) => {
-                    let node = current_node.clone();
-                    let node = node.unwrap();
-                    let node = node.as_ref();
-                    let mut node = node.borrow_mut();
-                    node.text = Some(String::from(r.trim()));
+                    last_text = r.clone();
+                    let node_ref = match current_node.clone() {
+                        Some(node_ref) => node_ref,
+                        None => continue,
+                    };
+                    let mut node = node_ref.as_ref().borrow_mut();
+                    if MarkupParser::<B>::has_inline_children(&node) {
+                        let text = MarkupParser::<B>::text_node(r, &node_ref, node.deep + 1);
+                        node.children.push(text);
+                        continue;
+                    }
+                    // blank texts (e.g. around comments) don't replace the text
+                    if r.trim().is_empty() {
+                        continue;
+                    }
+                    node.text = if MarkupParser::<B>::preserves_whitespace(&node) {
+                        // like an html `pre`, the line break after the tag is ignored
+                        let text = r.strip_prefix('\n').unwrap_or(r);
+                        Some(String::from(text.trim_end()))
+                    } else {
+                        Some(String::from(r.trim()))
+                    };
+                }
+                // spaces between inline elements (e.g. `<b>a</b> <i>b</i>`)
+                Ok(XmlEvent::Whitespace(ref r)) => {
+                    let node_ref = match current_node.clone() {
+                        Some(node_ref) => node_ref,
+                        None => continue,
+                    };
+                    let mut node = node_ref.as_ref().borrow_mut();
+                    if MarkupParser::<B>::has_inline_children(&node) {
+                        let text = MarkupParser::<B>::text_node(r, &node_ref, node.deep + 1);
+                        node.children.push(text);
+                    }
                 }
                 Ok(XmlEvent::EndElement { .. }) => {
+                    last_text.clear();
                     let p = MarkupParser::<B>::get_element(parent_node.clone());
+                    if MarkupParser::<B>::has_inline_children(&p) {
+                        let text = MarkupParser::<B>::inline_spans(&p, Style::default())
+                            .into_iter()
+                            .map(|(text, _)| text)
+                            .collect::<String>();
+                        let node = parent_node.clone().unwrap();
+                        node.as_ref().borrow_mut().text = Some(text.trim().to_string());
+                    }
+                    // the text after an element belongs to its parent
+                    current_node = p.parent_node.clone();
                     let q = p.clone();
                     if q.name.eq("styles") {
-                        global_styles = MarkupParser::<B>::process_styles(q);
+                        // the stylesheet in `src` goes first, the inline rules override it
+                        let src = extract_attribute(&q.attributes, "src");
+                        let mut rules = vec![];
+                        if !src.is_empty() {
+                            let src = Path::new(&path).parent().unwrap_or(Path::new("")).join(src);
+                            match std::fs::read_to_string(&src) {
+                                Ok(text) => rules = MarkupParser::<B>::parse_style_rules(&text),
+                                Err(e) => warnings.push(format!(
+                                    "Can't read stylesheet {}: {}",
+                                    src.display(),
+                                    e
+                                )),
+                            }
+                        }
+                        let text = q.text.unwrap_or_default();
+                        rules.extend(MarkupParser::<B>::parse_style_rules(&text));
+                        for (rulename, styles) in rules {
+                            global_styles.add_rule(rulename, styles);
+                        }
                     }
                     parent_node = p.parent_node;
                 }
-                Ok(XmlEvent::EndDocument { .. }) => {}
+                // declarations, comments and processing instructions are ignored
+                Ok(XmlEvent::StartDocument { .. })
+                | Ok(XmlEvent::Comment(_))
+                | Ok(XmlEvent::ProcessingInstruction { .. })
+                | Ok(XmlEvent::EndDocument) => {}
                 Err(e) => {
                     return MarkupParser {
                         path,
                         failed: true,
-                        error: Some(e.msg().to_string()),
+                        error: Some(e.to_string()),
+                        markup_error: Some(e),
                         root: None,
                         storage: None,
                         current: -1,
@@ -230,25 +524,67 @@ impl<B: Backend> MarkupParser<B> {
                         state: HashMap::new(),
                         global_styles: StylesStorage::new(),
                         fingerprint: String::from("<empty>"),
+                        unhandled_key_callback: None,
+                        focus_change_callback: None,
+                        tick_callback: None,
+                        ticks: 0,
+                        animated: false,
+                        visible_ids: vec![],
+                        styles_cache: RefCell::new(HashMap::new()),
+                        layout_fingerprint: String::from("<empty>"),
+                        layout_cache: vec![],
+                        layout_state_keys: vec![],
+                        warnings: vec![],
+                        layout_warnings: RefCell::new(vec![]),
+                        strict: false,
+                        keybindings: HashMap::new(),
+                        tick_rate: Duration::from_millis(200),
+                        key_repeat: true,
+                        alternate_screen: true,
+                        pending_actions: vec![],
+                        chained_actions: 0,
                     };
                 }
                 _ => {}
             };
         }
-        indexed_elements.sort_by(|e1, e2| e1.order.cmp(&e2.order));
-        let state = initial_state.unwrap_or(HashMap::new());
+        indexed_elements.sort_by_key(|e| e.order);
+        let mut state = initial_state.unwrap_or_default();
+        // open="true" only seeds the flag, the initial state wins
+        for show_flag in open_dialogs {
+            state.entry(show_flag).or_insert(true.into());
+        }
         actions.add_action("__change_tab".to_string(), |old_state, node_wrapper| {
             let mut state = old_state;
             if let Some(node) = node_wrapper {
                 let key = node.attributes.get("tabs-id").unwrap();
-                state.insert(format!("{}:index", key), node.id.clone());
+                state.insert(format!("{}:index", key), node.id.clone().into());
             }
             EventResponse::CLEANFOCUS(state)
         });
+        actions.add_action("__open_menu".to_string(), |old_state, node_wrapper| {
+            let mut state = old_state;
+            if let Some(node) = node_wrapper {
+                if let Some(key) = node.attributes.get("show") {
+                    state.insert(key.clone(), true.into());
+                }
+            }
+            EventResponse::STATE(state)
+        });
+        actions.add_action("__close_dialog".to_string(), |old_state, node_wrapper| {
+            let mut state = old_state;
+            if let Some(node) = node_wrapper {
+                if let Some(key) = node.attributes.get("dialog-show") {
+                    state.insert(key.clone(), false.into());
+                }
+            }
+            EventResponse::STATE(state)
+        });
         MarkupParser {
             path,
             failed: false,
             error: None,
+            markup_error: None,
             root: root_node,
             storage: Some(Rc::new(RefCell::new(storage))),
             current: -1,
@@ -258,27 +594,199 @@ impl<B: Backend> MarkupParser<B> {
             state,
             global_styles,
             fingerprint: String::from("<empty>"),
+            unhandled_key_callback: None,
+            focus_change_callback: None,
+            tick_callback: None,
+            ticks: 0,
+            animated: false,
+            visible_ids: vec![],
+            styles_cache: RefCell::new(HashMap::new()),
+            layout_fingerprint: String::from("<empty>"),
+            layout_cache: vec![],
+            layout_state_keys,
+            warnings,
+            layout_warnings: RefCell::new(vec![]),
+            strict: false,
+            keybindings,
+            tick_rate: Duration::from_millis(200),
+            key_repeat: true,
+            alternate_screen: true,
+            pending_actions: vec![],
+            chained_actions: 0,
+        }
+    }
+
+    /// Reads the XML events of a markup file, every `include` element is
+    /// replaced by the children of the root of the file in its `src` attribute
+    /// (relative to the including file). `includes` holds the files being read
+    /// to detect include cycles.
+    fn read_events(path: &Path, includes: &mut Vec<PathBuf>) -> Vec<Result<XmlEvent, MarkupError>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                let message = format!("Can't read {}: {}", path.display(), e);
+                return vec![Err(MarkupError::Load(message))];
+            }
+        };
+        let canonical = path.canonicalize().unwrap_or(path.to_path_buf());
+        if includes.contains(&canonical) {
+            let cycle: Vec<String> = includes
+                .iter()
+                .chain([&canonical])
+                .map(|x| x.display().to_string())
+                .collect();
+            let message = format!("Include cycle: {}", cycle.join(" -> "));
+            return vec![Err(MarkupError::Load(message))];
+        }
+        includes.push(canonical);
+        let mut events = vec![];
+        // depth inside an include element, its content is ignored
+        let mut skip_depth = 0;
+        for e in EventReader::new(BufReader::new(file)) {
+            match e {
+                Ok(XmlEvent::StartElement {
+                    ref name,
+                    ref attributes,
+                    ..
+                }) if skip_depth == 0 && name.local_name.eq("include") => {
+                    skip_depth = 1;
+                    let src = attributes
+                        .iter()
+                        .find(|attr| attr.name.local_name.eq("src"))
+                        .map(|attr| attr.value.clone())
+                        .unwrap_or_default();
+                    let src = path.parent().unwrap_or(Path::new("")).join(src);
+                    let included = MarkupParser::<B>::read_events(&src, includes);
+                    if let Some(Err(error)) = included.iter().find(|x| x.is_err()) {
+                        events.push(Err(error.clone()));
+                        break;
+                    }
+                    let first = included
+                        .iter()
+                        .position(|x| matches!(x, Ok(XmlEvent::StartElement { .. })));
+                    let last = included
+                        .iter()
+                        .rposition(|x| matches!(x, Ok(XmlEvent::EndElement { .. })));
+                    if let (Some(first), Some(last)) = (first, last) {
+                        events.extend(included[first + 1..last].iter().cloned());
+                    }
+                }
+                Ok(XmlEvent::StartElement { .. }) if skip_depth > 0 => skip_depth += 1,
+                Ok(XmlEvent::EndElement { .. }) if skip_depth > 0 => skip_depth -= 1,
+                Ok(_) if skip_depth > 0 => {}
+                Ok(event) => events.push(Ok(event)),
+                Err(e) => {
+                    let position = e.position();
+                    events.push(Err(MarkupError::Parse {
+                        line: position.row + 1,
+                        column: position.column + 1,
+                        message: e.msg().to_string(),
+                    }));
+                    break;
+                }
+            }
         }
+        includes.pop();
+        events
     }
 
     // Instance methods
     fn draw_block(
         &self,
         child: &MarkupElement,
-        _area: Rect,
+        area: Rect,
         focus: bool,
         active: bool,
         base_styles: Style,
-    ) -> Block {
-        let styles = MarkupParser::<B>::get_styles(&child.clone(), focus, active);
+    ) -> Block<'_> {
+        let styles = self.get_node_styles(child, focus, active);
         let styles = base_styles.patch(styles);
-        let title = extract_attribute(child.attributes.clone(), "title");
-        let border = extract_attribute(child.attributes.clone(), "border");
-        let border = MarkupParser::<B>::get_border(border.as_str());
-        let block = Block::default().title(title).style(styles).borders(border);
+        let title = self.interpolate(&extract_attribute(&child.attributes, "title"));
+        let border = MarkupParser::<B>::get_focused_border(child, focus);
+        let title = if MarkupParser::<B>::has_ellipsis(child) {
+            // the title is drawn between the corners
+            let corners = u16::from(border.contains(Borders::LEFT))
+                + u16::from(border.contains(Borders::RIGHT));
+            truncate_with_ellipsis(&title, area.width.saturating_sub(corners))
+        } else {
+            title
+        };
+        let border_type =
+            MarkupParser::<B>::get_focused_border_type(child, focus, BorderType::Plain);
+        let highlight = self.get_focus_highlight(child, focus);
+        let styles = match highlight {
+            Some(highlight) if border == Borders::NONE => styles.patch(highlight),
+            _ => styles,
+        };
+        let mut block = Block::default()
+            .title(title)
+            .style(styles)
+            .borders(border)
+            .border_type(border_type);
+        if let Some(border_styles) = self.get_highlighted_border_styles(child, highlight) {
+            block = block.border_style(border_styles);
+        }
         block
     }
 
+    /// The default look of the focused element: a bold yellow border (or
+    /// text, without borders). `None` when the element isn't focused or its
+    /// `focus_styles` or a `<name>:focus` rule set how it looks.
+    fn get_focus_highlight(&self, node: &MarkupElement, focus: bool) -> Option<Style> {
+        let styled = node.attributes.contains_key("focus_styles")
+            || self.global_styles.has_rule(format!("{}:focus", node.name));
+        let highlight = Style::default().fg(Color::Yellow);
+        if focus && !styled {
+            Some(highlight.add_modifier(Modifier::BOLD))
+        } else {
+            None
+        }
+    }
+
+    /// The `border-style` of an element with the focus highlight on top.
+    fn get_highlighted_border_styles(
+        &self,
+        node: &MarkupElement,
+        highlight: Option<Style>,
+    ) -> Option<Style> {
+        let border_styles = self.get_border_styles(node);
+        match highlight {
+            Some(highlight) => Some(border_styles.unwrap_or_default().patch(highlight)),
+            None => border_styles,
+        }
+    }
+
+    /// The `footer` attribute of a block, placed on its bottom border (inside
+    /// the corners) and clipped to it. `None` without footer or bottom border.
+    fn draw_footer(
+        &self,
+        child: &MarkupElement,
+        area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Option<(Paragraph<'_>, Rect)> {
+        let footer = extract_attribute(&child.attributes, "footer");
+        let border = MarkupParser::<B>::get_focused_border(child, focus);
+        if footer.is_empty() || !border.contains(Borders::BOTTOM) || area.height == 0 {
+            return None;
+        }
+        let left = u16::from(border.contains(Borders::LEFT));
+        let right = u16::from(border.contains(Borders::RIGHT));
+        let place = Rect::new(
+            area.x + left,
+            area.bottom() - 1,
+            area.width.saturating_sub(left + right),
+            1,
+        );
+        let styles = base_styles.patch(self.get_node_styles(child, focus, active));
+        let styles = match self.get_border_styles(child) {
+            Some(border_styles) => styles.patch(border_styles),
+            None => styles,
+        };
+        Some((Paragraph::new(Span::styled(footer, styles)), place))
+    }
+
     fn draw_paragraph(
         &self,
         child: &MarkupElement,
@@ -286,19 +794,67 @@ impl<B: Backend> MarkupParser<B> {
         focus: bool,
         active: bool,
         base_styles: Style,
-    ) -> Paragraph {
-        let styles = MarkupParser::<B>::get_styles(&child.clone(), focus, active);
+    ) -> Paragraph<'_> {
+        let styles = self.get_node_styles(child, focus, active);
         let styles = base_styles.patch(styles);
         let alignment = MarkupParser::<B>::get_alignment(&child.clone());
         let block = self.draw_block(&child.clone(), area, focus, active, base_styles);
-        let p = Paragraph::new(child.text.clone().unwrap_or(String::from("")))
+        let trim = !MarkupParser::<B>::preserves_whitespace(child);
+        let text = child.text.clone().unwrap_or(String::from(""));
+        // like buttons, blank lines before the text place it vertically
+        let inner = block.inner(area);
+        let text_rows = text_height(&text, inner.width, trim);
+        let free_rows = usize::from(inner.height).saturating_sub(text_rows);
+        let pad = match extract_attribute(&child.attributes, "valign").as_str() {
+            "center" => free_rows / 2,
+            "bottom" => free_rows,
+            _ => 0,
+        };
+        let mut lines = vec![Spans::from(""); pad];
+        if MarkupParser::<B>::has_inline_children(child) {
+            let mut spans = MarkupParser::<B>::inline_spans(child, Style::default());
+            if trim {
+                if let Some((text, _)) = spans.first_mut() {
+                    *text = text.trim_start().to_string();
+                }
+                if let Some((text, _)) = spans.last_mut() {
+                    *text = text.trim_end().to_string();
+                }
+            }
+            let mut line = vec![];
+            for (text, span_styles) in spans {
+                for (idx, piece) in text.split('\n').enumerate() {
+                    if idx > 0 {
+                        lines.push(Spans::from(std::mem::take(&mut line)));
+                    }
+                    if !piece.is_empty() {
+                        line.push(Span::styled(piece.to_string(), span_styles));
+                    }
+                }
+            }
+            lines.push(Spans::from(line));
+        } else {
+            lines.extend(text.split('\n').map(|line| Spans::from(line.to_string())));
+        }
+        let mut p = Paragraph::new(lines)
             .style(styles)
             .alignment(alignment)
-            .wrap(Wrap { trim: true })
+            .wrap(Wrap { trim })
             .block(block);
+        if MarkupParser::<B>::is_scrollable(child) {
+            p = p.scroll((self.get_scroll(child), 0));
+        }
         p
     }
 
+    /// Single line labels (block titles and button labels) are shortened with
+    /// `…` when they don't fit, with `overflow="ellipsis"` (or, in buttons,
+    /// `truncate="true"`).
+    fn has_ellipsis(node: &MarkupElement) -> bool {
+        extract_attribute(&node.attributes, "overflow") == "ellipsis"
+            || (node.name.eq("button") && extract_attribute(&node.attributes, "truncate") == "true")
+    }
+
     fn draw_button(
         &self,
         child: &MarkupElement,
@@ -306,33 +862,52 @@ impl<B: Backend> MarkupParser<B> {
         focus: bool,
         active: bool,
         base_styles: Style,
-    ) -> Paragraph {
-        let styles = MarkupParser::<B>::get_styles(&child.clone(), focus, active);
-        let styles = base_styles.patch(styles);
-        let mut elcnt = usize::from(area.height);
-        if area.height > 0 {
-            elcnt = usize::from(area.height / 2 - 1);
+    ) -> Paragraph<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let mut styles = base_styles.patch(styles);
+        if MarkupParser::<B>::is_disabled(child) {
+            styles = styles.add_modifier(Modifier::DIM);
         }
         let text = child.text.clone().unwrap_or(String::from(""));
+        let width = area.width.saturating_sub(2);
+        let lines = if MarkupParser::<B>::has_ellipsis(child) {
+            vec![truncate_with_ellipsis(text.trim(), width)]
+        } else {
+            wrap_text(&text, width)
+        };
+        // rows above the text to center it vertically
+        let free = usize::from(area.height.saturating_sub(2)).saturating_sub(lines.len());
+        let elcnt = free.div_ceil(2);
+        let text_styles = if focus {
+            styles.add_modifier(Modifier::UNDERLINED)
+        } else {
+            styles
+        };
         let mut lns_cntt = vec![];
         for _i in 0..elcnt {
             lns_cntt.push(Spans::from(""));
         }
-        lns_cntt.push(Spans::from(Span::styled(
-            text,
-            if focus {
-                styles.add_modifier(Modifier::UNDERLINED)
-            } else {
-                styles
-            },
-        )));
-        let block = Block::default()
+        for line in lines {
+            lns_cntt.push(Spans::from(Span::styled(line, text_styles)));
+        }
+        let border_type =
+            MarkupParser::<B>::get_focused_border_type(child, focus, BorderType::Rounded);
+        let highlight = self.get_focus_highlight(child, focus);
+        let mut block = Block::default()
             .style(styles)
             .borders(Borders::ALL)
-            .border_type(BorderType::Rounded);
+            .border_type(border_type);
+        if let Some(border_styles) = self.get_highlighted_border_styles(child, highlight) {
+            block = block.border_style(border_styles);
+        }
+        let alignment = if child.attributes.contains_key("align") {
+            MarkupParser::<B>::get_alignment(child)
+        } else {
+            Alignment::Center
+        };
         let p = Paragraph::new(lns_cntt)
             .style(styles)
-            .alignment(Alignment::Center)
+            .alignment(alignment)
             .block(block);
         p
     }
@@ -344,168 +919,929 @@ impl<B: Backend> MarkupParser<B> {
         focus: bool,
         active: bool,
         base_styles: Style,
-    ) -> Block {
-        let styles = MarkupParser::<B>::get_styles(&child.clone(), focus, active);
+    ) -> Block<'_> {
+        let styles = self.get_node_styles(child, focus, active);
         let styles = base_styles.patch(styles);
-        let block = Block::default()
+        let border_type = MarkupParser::<B>::get_node_border_type(child, BorderType::Double);
+        let mut block = Block::default()
             .style(styles)
             .borders(Borders::ALL)
-            .border_type(BorderType::Double);
+            .border_type(border_type);
+        if let Some(border_styles) = self.get_border_styles(child) {
+            block = block.border_style(border_styles);
+        }
         block
     }
 
     fn draw_tab_borders(
         &self,
-        _child: &MarkupElement,
+        child: &MarkupElement,
         _area: Rect,
         _focus: bool,
         _active: bool,
         _base_styles: Style,
-    ) -> Block {
+    ) -> Block<'_> {
+        let borders = if MarkupParser::<B>::is_vertical_tabs(child) {
+            Borders::RIGHT
+        } else {
+            Borders::BOTTOM
+        };
         let block = Block::default()
-            .borders(Borders::BOTTOM)
+            .borders(borders)
             .border_type(BorderType::Rounded);
         block
     }
 
-    fn draw_tab_item(
+    /// A horizontal rule, or a vertical one with `orientation="vertical"`.
+    fn draw_divider(
         &self,
         child: &MarkupElement,
-        _area: Rect,
         focus: bool,
         active: bool,
         base_styles: Style,
-    ) -> Paragraph {
-        let styles = Style::default()
-            .fg(Color::DarkGray)
-            ;
-
-        let styles = if active {
-            styles
-                .fg(Color::Gray)
-                .add_modifier(Modifier::BOLD)
+    ) -> Block<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
+        let vertical = extract_attribute(&child.attributes, "orientation").eq("vertical");
+        let borders = if vertical {
+            Borders::LEFT
         } else {
-            styles
+            Borders::TOP
         };
+        let border_type = MarkupParser::<B>::get_node_border_type(child, BorderType::Plain);
+        Block::default()
+            .borders(borders)
+            .border_type(border_type)
+            .border_style(styles)
+    }
+
+    fn draw_skeleton(
+        &self,
+        child: &MarkupElement,
+        area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Paragraph<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.fg(Color::DarkGray).patch(styles);
+        let width = usize::from(area.width);
+        let lines = extract_attribute(&child.attributes, "lines")
+            .parse::<usize>()
+            .unwrap_or(usize::from(area.height));
+        let shimmer_width = 4;
+        let shimmer_start = (self.ticks as usize * 2) % (width + shimmer_width);
+        let bars: Vec<Spans> = (0..lines)
+            .map(|line| {
+                // the last bar is shorter, like the end of a paragraph
+                let bar_width = if line + 1 == lines && lines > 1 {
+                    width * 2 / 3
+                } else {
+                    width
+                };
+                let bar: String = (0..bar_width)
+                    .map(|col| {
+                        if col + shimmer_width >= shimmer_start && col < shimmer_start {
+                            '▒'
+                        } else {
+                            '░'
+                        }
+                    })
+                    .collect();
+                Spans::from(Span::styled(bar, styles))
+            })
+            .collect();
+        Paragraph::new(bars).style(styles)
+    }
 
+    fn draw_menu_item(
+        &self,
+        child: &MarkupElement,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Paragraph<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
         let styles = if focus {
-            styles
-                .fg(Color::White)
-                // .add_modifier(Modifier::BOLD)
+            styles.add_modifier(Modifier::REVERSED)
         } else {
             styles
         };
-        let styles = styles.patch(base_styles);
-        let text = child.text.clone();
-        let text = text.unwrap_or("Tab".to_string());
-        let block = Block::default()
-            .style(styles)
-            .borders(Borders::TOP | Borders::RIGHT | Borders::LEFT)
-            .border_type(BorderType::Rounded);
-        let p = Paragraph::new(text)
-            .style(styles)
-            .alignment(Alignment::Center)
-            .block(block);
-        p
+        let text = child.text.clone().unwrap_or_default();
+        Paragraph::new(format!(" {}", text)).style(styles)
     }
 
-    fn go_next(&mut self) -> i32 {
-        let size = i32::try_from(self.indexed_elements.len()).unwrap() - 2;
-        if self.current > size {
-            self.current = -1;
-        } else {
-            self.current += 1;
-        }
-        self.current
+    fn draw_radio_group(
+        &self,
+        child: &MarkupElement,
+        area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Paragraph<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
+        let block = self.draw_block(child, area, focus, active, base_styles);
+        let (radios, selected) = self.get_radios(child);
+        let lines: Vec<Spans> = radios
+            .into_iter()
+            .enumerate()
+            .map(|(position, (_, text))| {
+                if position == selected {
+                    let styles = if focus {
+                        styles.add_modifier(Modifier::UNDERLINED)
+                    } else {
+                        styles
+                    };
+                    Spans::from(Span::styled(format!("(•) {}", text), styles))
+                } else {
+                    Spans::from(Span::styled(format!("( ) {}", text), styles))
+                }
+            })
+            .collect();
+        Paragraph::new(lines).style(styles).block(block)
     }
 
-    fn go_prev(&mut self) -> i32 {
-        let size = i32::try_from(self.indexed_elements.len()).unwrap() - 1;
-        if self.current < 0 {
-            self.current = size;
-        } else {
-            self.current -= 1;
+    /// Tells if a spinner is running: always when it has no `bind` attribute,
+    /// otherwise while the bound state value is `true`.
+    fn is_spinning(&self, node: &MarkupElement) -> bool {
+        extract_attribute(&node.attributes, "bind").is_empty()
+            || self.get_bound_value(node).as_bool().unwrap_or(false)
+    }
+
+    fn draw_spinner(
+        &self,
+        child: &MarkupElement,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Paragraph<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
+        if !self.is_spinning(child) {
+            return Paragraph::new("").style(styles);
         }
-        self.current
+        let spinner_style = extract_attribute(&child.attributes, "spinner-style");
+        let frames: Vec<char> = match spinner_style.as_str() {
+            "line" => "-\\|/".chars().collect(),
+            "bar" => "▁▂▃▄▅▆▇█▇▆▅▄▃▂".chars().collect(),
+            _ => "⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏".chars().collect(),
+        };
+        let frame = frames[self.ticks as usize % frames.len()];
+        let label = extract_attribute(&child.attributes, "label");
+        let text = if label.is_empty() {
+            frame.to_string()
+        } else {
+            format!("{} {}", frame, label)
+        };
+        Paragraph::new(text).style(styles)
     }
 
-    fn do_action(&mut self) -> EventResponse {
-        if self.current > -1 {
-            let current = self.indexed_elements[self.current as usize].clone();
-            let action = extract_attribute(current.attributes.clone(), "action");
-            if self.actions.has_action(action.clone()) {
-                info!("Executing {}", action);
-                let new_state = self
-                    .actions
-                    .execute(action, self.state.clone(), Some(current));
-                if let Some(event_response) = new_state {
-                    return event_response;
-                }
+    /// Replaces the `{{key}}` placeholders of a text with the state values,
+    /// missing keys give an empty text.
+    fn interpolate(&self, text: &str) -> String {
+        let mut res = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let end = match rest[start..].find("}}") {
+                Some(end) => end,
+                None => break,
+            };
+            let key = rest[start + 2..start + end].trim();
+            res.push_str(&rest[..start]);
+            if let Some(value) = self.state.get(key) {
+                res.push_str(&value.to_string());
             }
+            rest = &rest[start + end + 2..];
         }
-        EventResponse::NOOP
+        res.push_str(rest);
+        res
     }
 
-    fn get_element_styles(&self, node: &MarkupElement, focus: bool, active: bool) -> Style {
-        let name = node.name.clone();
-        let parent = node.parent_node.clone();
-        let parent_styles = if let Some(nref) = parent {
-            let parent = MarkupParser::<B>::extract_element(&nref);
-            self.get_element_styles(&parent, focus, active)
+    /// Reads the state value bound to an element through its `bind` attribute.
+    fn get_bound_value(&self, node: &MarkupElement) -> StateValue {
+        let key = extract_attribute(&node.attributes, "bind");
+        self.state.get(&key).cloned().unwrap_or_default()
+    }
+
+    fn draw_gauge(
+        &self,
+        child: &MarkupElement,
+        area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Gauge<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
+        let percent = self.get_bound_value(child).as_float().unwrap_or(0.0);
+        let percent = if percent.is_finite() {
+            percent.clamp(0.0, 100.0)
         } else {
-            Style::default()
+            0.0
         };
-        let rulename = if focus {
-            format!("{}{}", name, if focus { ":focus" } else { "" })
-        } else if active {
-            format!("{}{}", name, if active { ":active" } else { "" })
+        let label = extract_attribute(&child.attributes, "label");
+        let label = if label.is_empty() {
+            format!("{:.0}%", percent)
         } else {
-            name
+            format!("{} {:.0}%", label, percent)
         };
-        let base_styles = parent_styles.patch(self.global_styles.get_rule(rulename));
-        let rulename = format!("#{}", node.id);
-        let elm_styles = self.global_styles.get_rule(rulename);
+        let block = self.draw_block(&child.clone(), area, focus, active, base_styles);
+        Gauge::default()
+            .block(block)
+            .gauge_style(styles)
+            .ratio(percent / 100.0)
+            .label(label)
+    }
 
-        base_styles.patch(elm_styles)
+    /// Reads the percent of a progress bar, `None` while the bound state value
+    /// isn't a number (e.g. `"loading"`) and the progress is unknown.
+    fn get_progress_percent(&self, node: &MarkupElement) -> Option<f64> {
+        let percent = self.get_bound_value(node).as_float()?;
+        if percent.is_finite() {
+            Some(percent.clamp(0.0, 100.0))
+        } else {
+            None
+        }
     }
 
-    fn draw_element(&mut self, frame: &mut Frame<B>, area: Rect, node: &MarkupElement) -> bool {
-        let name = node.name.clone();
-        let name = name.as_str();
-        let storage = self.storage.clone();
-        let storage = storage.unwrap();
-        let storage = storage.as_ref();
-        let storage = storage.borrow_mut();
-        if storage.has_component(name) {
-            storage.render(name, frame);
-            true
+    /// Draws an unknown progress: a segment bouncing from side to side, it
+    /// moves on every tick.
+    fn draw_progress(
+        &self,
+        child: &MarkupElement,
+        area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Paragraph<'_> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
+        let block = self.draw_block(&child.clone(), area, focus, active, base_styles);
+        let inner = block.inner(area);
+        let label = extract_attribute(&child.attributes, "label");
+        let prefix = if label.is_empty() {
+            String::new()
         } else {
-            let mut cid = "".to_owned();
-            if self.current > -1 {
-                cid = self.indexed_elements[self.current as usize].id.clone();
-            }
-            let is_focused_node = node.id.eq(&cid);
-            let is_active_tab = if node.parent_node.is_some() {
-                let parent_node: MarkupElement =
-                    node.parent_node.clone().unwrap().as_ref().borrow().clone();
-                let parent_id = parent_node.id;
-                let state_elm = format!("{}:index", parent_id);
-                let current = self.state.get(&state_elm);
-                if let Some(current) = current {
-                    let currval = current;
-                    currval.eq(&node.id)
-                } else {
-                    false
-                }
+            format!("{} ", label)
+        };
+        let width = (inner.width as usize).saturating_sub(prefix.width());
+        let segment = (width / 4).max(1).min(width);
+        let steps = width - segment;
+        let position = if steps == 0 {
+            0
+        } else {
+            let step = self.ticks as usize % (2 * steps);
+            if step > steps {
+                2 * steps - step
             } else {
-                false
-            };
-            let base_styles = self.get_element_styles(node, is_focused_node, is_active_tab);
-            match name {
-                "container" | "block" => {
-                    let widget = self.draw_block(node, area, is_focused_node, false, base_styles);
-                    frame.render_widget(Clear, area);
+                step
+            }
+        };
+        let bar = format!(
+            "{}{}{}",
+            " ".repeat(position),
+            "█".repeat(segment),
+            " ".repeat(steps - position)
+        );
+        Paragraph::new(format!("{}{}", prefix, bar))
+            .style(styles)
+            .block(block)
+    }
+
+    fn draw_sparkline<'a>(
+        &'a self,
+        child: &MarkupElement,
+        data: &'a [u64],
+        area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Sparkline<'a> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
+        let block = self.draw_block(&child.clone(), area, focus, active, base_styles);
+        let sparkline = Sparkline::default().block(block).style(styles).data(data);
+        match extract_attribute(&child.attributes, "max").parse::<u64>() {
+            Ok(max) => sparkline.max(max),
+            Err(_) => sparkline,
+        }
+    }
+
+    fn draw_barchart<'a>(
+        &'a self,
+        child: &MarkupElement,
+        data: &'a [(&'a str, u64)],
+        area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> BarChart<'a> {
+        let styles = self.get_node_styles(child, focus, active);
+        let styles = base_styles.patch(styles);
+        let block = self.draw_block(&child.clone(), area, focus, active, base_styles);
+        let mut barchart = BarChart::default()
+            .block(block)
+            .bar_style(styles)
+            .data(data);
+        if let Ok(bar_width) = extract_attribute(&child.attributes, "bar-width").parse::<u16>() {
+            barchart = barchart.bar_width(bar_width.max(1));
+        }
+        if let Ok(bar_gap) = extract_attribute(&child.attributes, "bar-gap").parse::<u16>() {
+            barchart = barchart.bar_gap(bar_gap);
+        }
+        match extract_attribute(&child.attributes, "max").parse::<u64>() {
+            Ok(max) => barchart.max(max),
+            Err(_) => barchart,
+        }
+    }
+
+    /// A frame smaller than the `min-width`/`min-height` of the root element
+    /// (`MIN_FRAME_WIDTH` x `MIN_FRAME_HEIGHT` by default) only shows a
+    /// "Terminal too small" message.
+    fn draw_too_small(&self, root: &MarkupElement, area: Rect) -> Option<(Paragraph<'_>, Rect)> {
+        let min_width = extract_attribute(&root.attributes, "min-width")
+            .parse::<u16>()
+            .unwrap_or(MIN_FRAME_WIDTH);
+        let min_height = extract_attribute(&root.attributes, "min-height")
+            .parse::<u16>()
+            .unwrap_or(MIN_FRAME_HEIGHT);
+        if area.width >= min_width && area.height >= min_height {
+            return None;
+        }
+        let message = Paragraph::new("Terminal too small")
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        Some((message, area))
+    }
+
+    /// With `scrollbar="true"` a scrollable element whose content doesn't fit
+    /// gets a track and a thumb on its right edge, placed by its scroll offset.
+    fn draw_scrollbar(&self, node: &MarkupElement, area: Rect) -> Option<(Paragraph<'_>, Rect)> {
+        if !MarkupParser::<B>::is_scrollable(node)
+            || extract_attribute(&node.attributes, "scrollbar") != "true"
+        {
+            return None;
+        }
+        let inner = self
+            .draw_block(node, area, false, false, Style::default())
+            .inner(area);
+        let text = node.text.clone().unwrap_or_default();
+        let trim = !MarkupParser::<B>::preserves_whitespace(node);
+        let content = text_height(&text, inner.width, trim);
+        let rows = usize::from(inner.height);
+        if rows == 0 || inner.width == 0 || content <= rows {
+            return None;
+        }
+        let thumb = (rows * rows / content).max(1);
+        let offset = usize::from(self.get_scroll(node)).min(content - rows);
+        let start = (rows - thumb) * offset / (content - rows);
+        let lines: Vec<Spans> = (0..rows)
+            .map(|row| {
+                let glyph = if row >= start && row < start + thumb {
+                    "█"
+                } else {
+                    "│"
+                };
+                Spans::from(glyph)
+            })
+            .collect();
+        let bar_area = Rect::new(area.right() - 1, inner.y, 1, inner.height);
+        Some((Paragraph::new(lines), bar_area))
+    }
+
+    /// A markup drawing nothing (e.g. a root with only `styles` or an unknown
+    /// root element) shows a message instead of a blank screen.
+    fn draw_no_widgets(area: Rect) -> (Paragraph<'static>, Rect) {
+        let message = "Layout produced no widgets, check your root element";
+        let rows = text_height(message, area.width, true);
+        let pad = usize::from(area.height).saturating_sub(rows) / 2;
+        let mut lines = vec![Spans::from(""); pad];
+        lines.push(Spans::from(message));
+        let message = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        (message, area)
+    }
+
+    fn draw_tab_item(
+        &self,
+        child: &MarkupElement,
+        _area: Rect,
+        focus: bool,
+        active: bool,
+        base_styles: Style,
+    ) -> Paragraph<'_> {
+        let styles = Style::default().fg(Color::DarkGray);
+        let styles = if active {
+            styles.fg(Color::Gray).add_modifier(Modifier::BOLD)
+        } else {
+            styles
+        };
+        let styles = if focus {
+            styles.fg(Color::White)
+        } else {
+            styles
+        };
+        // `tab-item:active`/`tab-item:focus` rules and the element styles win
+        let styles = styles
+            .patch(base_styles)
+            .patch(self.get_node_styles(child, focus, active));
+        let text = child.text.clone();
+        let text = text.unwrap_or("Tab".to_string());
+        let borders = if MarkupParser::<B>::is_vertical_tabs(child) {
+            Borders::TOP | Borders::BOTTOM | Borders::LEFT
+        } else {
+            Borders::TOP | Borders::RIGHT | Borders::LEFT
+        };
+        let block = Block::default()
+            .style(styles)
+            .borders(borders)
+            .border_type(BorderType::Rounded);
+        let p = Paragraph::new(text)
+            .style(styles)
+            .alignment(Alignment::Center)
+            .block(block);
+        p
+    }
+
+    /// Index of the last focusable element, -1 when there is nothing to focus.
+    fn last_index(&self) -> i32 {
+        i32::try_from(self.indexed_elements.len()).unwrap() - 1
+    }
+
+    /// Checks if the indexed element at `index` was drawn in the last render
+    /// (elements inside hidden tabs can't get the focus).
+    fn is_visible_index(&self, index: i32) -> bool {
+        if index < 0 || self.visible_ids.is_empty() {
+            return true;
+        }
+        let elm = &self.indexed_elements[index as usize];
+        self.visible_ids.contains(&elm.id)
+    }
+
+    /// Tells if the focus can stop at an index: visible elements, and -1
+    /// (nothing focused) unless a dialog is open, dialogs keep a button focused.
+    fn is_focus_stop(&self, index: i32) -> bool {
+        (index > -1 || self.contexts.is_empty()) && self.is_visible_index(index)
+    }
+
+    /// Moves the focus to the next visible indexed element. After the last
+    /// element the focus goes to -1 (nothing focused) before starting again.
+    fn go_next(&mut self) -> i32 {
+        let previous = self.focused_id();
+        for _ in 0..=self.indexed_elements.len() {
+            if self.current >= self.last_index() {
+                self.current = -1;
+            } else {
+                self.current += 1;
+            }
+            if self.is_focus_stop(self.current) {
+                break;
+            }
+        }
+        self.notify_focus_change(previous);
+        self.current
+    }
+
+    /// Moves the focus to the previous visible indexed element. Before the first
+    /// element the focus goes to -1 (nothing focused) and then to the last one.
+    fn go_prev(&mut self) -> i32 {
+        let previous = self.focused_id();
+        for _ in 0..=self.indexed_elements.len() {
+            if self.current < 0 || self.current > self.last_index() {
+                self.current = self.last_index();
+            } else {
+                self.current -= 1;
+            }
+            if self.is_focus_stop(self.current) {
+                break;
+            }
+        }
+        self.notify_focus_change(previous);
+        self.current
+    }
+
+    /// Advances the animation counter used by skeletons, spinners and progress
+    /// bars and calls the `set_on_tick` callback, the event loop calls it on
+    /// every tick.
+    pub fn tick(&mut self) {
+        self.ticks = self.ticks.wrapping_add(1);
+        self.chained_actions = 0;
+        if let Some(mut callback) = self.tick_callback.take() {
+            callback(self);
+            // the callback could register a new one
+            if self.tick_callback.is_none() {
+                self.tick_callback = Some(callback);
+            }
+        }
+    }
+
+    /// Calls the `set_on_focus_change` callback when the focused element is
+    /// not `previous` anymore.
+    fn notify_focus_change(&mut self, previous: Option<String>) {
+        let current = self.focused_id();
+        if previous != current {
+            if let Some(callback) = self.focus_change_callback.as_mut() {
+                callback(previous.as_deref(), current.as_deref(), &mut self.state);
+                self.fingerprint = String::from("<>");
+            }
+        }
+    }
+
+    /// Identifier of the focused element, if any.
+    pub fn focused_id(&self) -> Option<String> {
+        usize::try_from(self.current)
+            .ok()
+            .and_then(|idx| self.indexed_elements.get(idx))
+            .map(|current| current.id.clone())
+    }
+
+    /// Name of the focused element (e.g. `button`), `None` without focus.
+    pub fn focused_name(&self) -> Option<String> {
+        usize::try_from(self.current)
+            .ok()
+            .and_then(|idx| self.indexed_elements.get(idx))
+            .map(|current| current.name.clone())
+    }
+
+    /// Checks if the focused element uses the arrow keys itself (e.g. editable
+    /// widgets), in that case arrows don't move the focus.
+    pub fn focused_consumes_arrows(&self) -> bool {
+        if self.current > -1 {
+            let current = &self.indexed_elements[self.current as usize];
+            ARROW_CONSUMER_NAMES.contains(&current.name.as_str())
+        } else {
+            false
+        }
+    }
+
+    /// Checks if the focused element is a scrollable paragraph, in that case
+    /// Up/Down scroll its content instead of moving the focus.
+    fn focused_is_scrollable(&self) -> bool {
+        if self.current > -1 {
+            let current = &self.indexed_elements[self.current as usize];
+            MarkupParser::<B>::is_scrollable(current)
+        } else {
+            false
+        }
+    }
+
+    /// Checks if the focused element is a radio group, in that case Up/Down
+    /// change the selected radio instead of moving the focus.
+    fn focused_is_radio_group(&self) -> bool {
+        usize::try_from(self.current)
+            .ok()
+            .and_then(|idx| self.indexed_elements.get(idx))
+            .map(|current| current.name.eq("radio-group"))
+            .unwrap_or(false)
+    }
+
+    /// The state key holding the value of a radio group: its `bind`
+    /// attribute or `<id>:value`.
+    fn get_radio_group_key(node: &MarkupElement) -> String {
+        let key = extract_attribute(&node.attributes, "bind");
+        if key.is_empty() {
+            format!("{}:value", node.id)
+        } else {
+            key
+        }
+    }
+
+    /// The `value` of the radios of a group with their position, the first
+    /// radio is selected when the state has no value for the group.
+    fn get_radios(&self, node: &MarkupElement) -> (Vec<(String, String)>, usize) {
+        let radios: Vec<(String, String)> = node
+            .children
+            .iter()
+            .map(|child| child.as_ref().borrow())
+            .filter(|child| child.name.eq("radio"))
+            .map(|child| {
+                let value = extract_attribute(&child.attributes, "value");
+                (value, child.text.clone().unwrap_or_default())
+            })
+            .collect();
+        let key = MarkupParser::<B>::get_radio_group_key(node);
+        let selected = self
+            .state
+            .get(&key)
+            .and_then(|value| radios.iter().position(|radio| value.eq(&radio.0)))
+            .unwrap_or(0);
+        (radios, selected)
+    }
+
+    /// Moves the selection of the focused radio group by `delta` radios and
+    /// writes the value of the selected radio into the state.
+    fn select_radio(&mut self, delta: i32) {
+        let id = self.indexed_elements[self.current as usize].id.clone();
+        // indexed elements are cloned before their children are parsed, so
+        // the radios are read from the tree
+        if let Some(group) = self.find_element(&id) {
+            let (radios, selected) = self.get_radios(&group);
+            if radios.is_empty() {
+                return;
+            }
+            let last = i32::try_from(radios.len()).unwrap_or(i32::MAX) - 1;
+            let selected = (i32::try_from(selected).unwrap_or(0) + delta).clamp(0, last);
+            let value = radios[selected as usize].0.clone();
+            self.state
+                .insert(MarkupParser::<B>::get_radio_group_key(&group), value.into());
+        }
+    }
+
+    /// The tab items of the tabs owning the focused tab or, otherwise, of the
+    /// first tabs on screen, with the identifier of the tabs. The items keep
+    /// their order in the tabs header, disabled ones included.
+    fn current_tab_items(&self) -> Option<(String, Vec<MarkupElement>)> {
+        let tab_items: Vec<MarkupElement> = self
+            .indexed_elements
+            .iter()
+            .filter(|elm| elm.name.eq("tab-item") && self.visible_ids.contains(&elm.id))
+            .cloned()
+            .collect();
+        let focused_tabs = usize::try_from(self.current)
+            .ok()
+            .and_then(|current| self.indexed_elements.get(current))
+            .filter(|elm| elm.name.eq("tab-item"))
+            .map(|elm| extract_attribute(&elm.attributes, "tabs-id"));
+        let tabs_id = match focused_tabs {
+            Some(tabs_id) => tabs_id,
+            None => extract_attribute(&tab_items.first()?.attributes, "tabs-id"),
+        };
+        let tabs = self.find_element(&tabs_id)?;
+        let header = MarkupParser::<B>::extract_element(tabs.children.first()?);
+        let tab_items = header
+            .children
+            .iter()
+            .map(MarkupParser::<B>::extract_element)
+            .filter(|elm| elm.name.eq("tab-item"))
+            .collect();
+        Some((tabs_id, tab_items))
+    }
+
+    /// Selects the tab at `position` (starting at 1) of the current tabs (see
+    /// `current_tab_items`). Returns the `__change_tab` response or `None`
+    /// when there is no such tab.
+    fn select_tab_by_position(&mut self, position: usize) -> Option<EventResponse> {
+        let (_, tab_items) = self.current_tab_items()?;
+        let tab_item = tab_items
+            .into_iter()
+            .nth(position.checked_sub(1)?)
+            .filter(|elm| !MarkupParser::<B>::is_disabled(elm))?;
+        self.actions.execute(
+            "__change_tab".to_string(),
+            self.state.clone(),
+            Some(tab_item),
+        )
+    }
+
+    /// Selects the tab `delta` positions after the active one of the current
+    /// tabs, wrapping around at the ends.
+    fn select_tab_by_offset(&mut self, delta: i32) -> Option<EventResponse> {
+        let (tabs_id, mut tab_items) = self.current_tab_items()?;
+        tab_items.retain(|elm| !MarkupParser::<B>::is_disabled(elm));
+        let active = self
+            .state
+            .get(&format!("{}:index", tabs_id))
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        let count = i32::try_from(tab_items.len())
+            .ok()
+            .filter(|count| *count > 0)?;
+        let position = tab_items
+            .iter()
+            .position(|elm| elm.id.eq(&active))
+            .and_then(|position| i32::try_from(position).ok())
+            .unwrap_or(0);
+        let position = usize::try_from((position + delta).rem_euclid(count)).ok()?;
+        let tab_item = tab_items.into_iter().nth(position)?;
+        self.actions.execute(
+            "__change_tab".to_string(),
+            self.state.clone(),
+            Some(tab_item),
+        )
+    }
+
+    /// Reads the scroll offset of an element from the `<id>:scroll` state key.
+    fn get_scroll(&self, node: &MarkupElement) -> u16 {
+        let key = format!("{}:scroll", node.id);
+        self.state
+            .get(&key)
+            .and_then(StateValue::as_int)
+            .and_then(|offset| u16::try_from(offset).ok())
+            .unwrap_or(0)
+    }
+
+    /// Moves the scroll offset of the focused element by `delta` lines.
+    fn scroll_focused(&mut self, delta: i32) {
+        let id = self.indexed_elements[self.current as usize].id.clone();
+        self.scroll_element(&id, delta);
+    }
+
+    /// Moves the scroll offset of an element by `delta` lines, the offset is
+    /// clamped to the lines of its content.
+    fn scroll_element(&mut self, id: &str, delta: i32) {
+        // indexed elements are cloned before their text is parsed, so the
+        // text is read from the tree
+        if let Some(element) = self.find_element(id) {
+            let lines = element.text.clone().unwrap_or_default().lines().count();
+            let max_offset = i32::try_from(lines).unwrap_or(i32::MAX).max(1) - 1;
+            let offset = (i32::from(self.get_scroll(&element)) + delta).clamp(0, max_offset);
+            self.state
+                .insert(format!("{}:scroll", element.id), i64::from(offset).into());
+        }
+    }
+
+    /// Looks for the scrollable element drawn at a position in the last render,
+    /// the last drawn (topmost) element wins.
+    fn scrollable_at(&self, column: u16, row: u16) -> Option<String> {
+        self.layout_cache
+            .iter()
+            .rev()
+            .filter(|(_, node)| self.visible_ids.contains(&node.id))
+            .find(|(area, node)| {
+                MarkupParser::<B>::is_scrollable(node)
+                    && column >= area.x
+                    && column < area.right()
+                    && row >= area.y
+                    && row < area.bottom()
+            })
+            .map(|(_, node)| node.id.clone())
+    }
+
+    fn do_action(&mut self) -> EventResponse {
+        if self.current > -1 {
+            let current = self.indexed_elements[self.current as usize].clone();
+            if MarkupParser::<B>::is_disabled(&current) {
+                return EventResponse::NOOP;
+            }
+            // "first;second" runs both actions, the second one receives the
+            // state returned by the first one
+            let actions = extract_attribute(&current.attributes, "action");
+            let mut state: Option<HashMap<String, StateValue>> = None;
+            let mut clean_focus = false;
+            for action in actions.split(';').map(str::trim).filter(|x| !x.is_empty()) {
+                if !self.actions.has_action(action.to_string()) {
+                    warn!("Unknown action \"{}\"", action);
+                    continue;
+                }
+                info!("Executing {}", action);
+                let current_state = state.clone().unwrap_or_else(|| self.state.clone());
+                let response =
+                    self.actions
+                        .execute(action.to_string(), current_state, Some(current.clone()));
+                match response {
+                    Some(EventResponse::QUIT) => return EventResponse::QUIT,
+                    Some(EventResponse::STATE(new_state)) => state = Some(new_state),
+                    Some(EventResponse::CLEANFOCUS(new_state)) => {
+                        state = Some(new_state);
+                        clean_focus = true;
+                    }
+                    Some(EventResponse::THEN(new_state, next_action)) => {
+                        state = Some(new_state);
+                        self.pending_actions.push(next_action);
+                    }
+                    Some(EventResponse::NOOP) | None => {}
+                }
+            }
+            match state {
+                Some(state) if clean_focus => return EventResponse::CLEANFOCUS(state),
+                Some(state) => return EventResponse::STATE(state),
+                None => {}
+            }
+        }
+        EventResponse::NOOP
+    }
+
+    /// Runs a registered action as if the focused element triggered it and
+    /// applies its response, the response is returned so a `ui_loop_ctx`
+    /// callback can forward it (e.g. a `QUIT`).
+    pub fn dispatch(&mut self, action: &str) -> EventResponse {
+        let current = usize::try_from(self.current)
+            .ok()
+            .and_then(|idx| self.indexed_elements.get(idx))
+            .cloned();
+        let response = self
+            .actions
+            .execute(action.to_string(), self.state.clone(), current);
+        match response {
+            Some(response) => {
+                self.apply_response(&response);
+                response
+            }
+            None => {
+                warn!("Unknown action \"{}\"", action);
+                EventResponse::NOOP
+            }
+        }
+    }
+
+    /// Styles of the borders from the `border-style` attribute, if present.
+    fn get_border_styles(&self, node: &MarkupElement) -> Option<Style> {
+        let styles_text = extract_attribute(&node.attributes, "border-style");
+        if styles_text.is_empty() {
+            None
+        } else {
+            Some(self.parse_styles(styles_text))
+        }
+    }
+
+    /// Same as `get_styles` but the parsed styles are cached by their text, so
+    /// the attributes aren't parsed again on every frame.
+    fn get_node_styles(&self, node: &MarkupElement, focus: bool, active: bool) -> Style {
+        MarkupParser::<B>::get_styles_keys(focus, active)
+            .into_iter()
+            .fold(Style::default(), |styles, key| {
+                let styles_text = extract_attribute(&node.attributes, key);
+                styles.patch(self.parse_styles(styles_text))
+            })
+    }
+
+    fn parse_styles(&self, styles_text: String) -> Style {
+        if let Some(styles) = self.styles_cache.borrow().get(&styles_text) {
+            return *styles;
+        }
+        let styles = MarkupParser::<B>::generate_styles(styles_text.clone());
+        self.styles_cache.borrow_mut().insert(styles_text, styles);
+        styles
+    }
+
+    fn get_element_styles(&self, node: &MarkupElement, focus: bool, active: bool) -> Style {
+        let name = node.name.clone();
+        let parent_styles = if let Some(parent) = node.parent() {
+            self.get_element_styles(&parent, focus, active)
+        } else {
+            Style::default()
+        };
+        // the `:active` and `:focus` rules are applied over the plain one
+        let mut base_styles = parent_styles.patch(self.global_styles.get_rule(name.clone()));
+        if active {
+            base_styles =
+                base_styles.patch(self.global_styles.get_rule(format!("{}:active", name)));
+        }
+        if focus {
+            base_styles = base_styles.patch(self.global_styles.get_rule(format!("{}:focus", name)));
+        }
+        let rulename = format!("#{}", node.id);
+        let elm_styles = self.global_styles.get_rule(rulename);
+
+        base_styles.patch(elm_styles)
+    }
+
+    /// Clears the area of an element before drawing it, unless the element has
+    /// `clear="false"` (e.g. a label layered over another element).
+    fn clear_area(frame: &mut Frame<B>, node: &MarkupElement, area: Rect) {
+        if !extract_attribute(&node.attributes, "clear").eq("false") {
+            frame.render_widget(Clear, area);
+        }
+    }
+
+    fn draw_element(&mut self, frame: &mut Frame<B>, area: Rect, node: &MarkupElement) -> bool {
+        let name = node.name.clone();
+        let name = name.as_str();
+        let storage = self.storage.clone();
+        let storage = storage.unwrap();
+        let storage = storage.as_ref();
+        let storage = storage.borrow_mut();
+        if storage.has_component(name) {
+            storage.render(node, area, &self.state, frame).is_some()
+        } else {
+            let mut cid = "".to_owned();
+            if self.current > -1 {
+                cid = self.indexed_elements[self.current as usize].id.clone();
+            }
+            let is_focused_node = node.id.eq(&cid);
+            let is_active_tab = if name.eq("tab-content") {
+                // a tab content is active while its tab is selected
+                let tabs_id = extract_attribute(&node.attributes, "tabs-id");
+                let selected = self.state.get(&format!("{}:index", tabs_id));
+                let me = extract_attribute(&node.attributes, "for");
+                selected.map(|selected| selected.eq(&me)).unwrap_or(false)
+            } else if let Some(parent_node) = node.parent() {
+                let parent_id = parent_node.id;
+                let state_elm = format!("{}:index", parent_id);
+                let current = self.state.get(&state_elm);
+                if let Some(current) = current {
+                    let currval = current;
+                    currval.eq(&node.id)
+                } else {
+                    false
+                }
+            } else {
+                false
+            };
+            let base_styles = self.get_element_styles(node, is_focused_node, is_active_tab);
+            match name {
+                "container" | "block" => {
+                    let widget =
+                        self.draw_block(node, area, is_focused_node, is_active_tab, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
                     frame.render_widget(widget, area);
+                    let footer = self.draw_footer(node, area, is_focused_node, false, base_styles);
+                    if let Some((widget, place)) = footer {
+                        frame.render_widget(widget, place);
+                    }
                     true
                 }
                 "tabs-borders" => {
@@ -515,22 +1851,35 @@ impl<B: Backend> MarkupParser<B> {
                     true
                 }
                 "p" => {
-                    let widget = self.draw_paragraph(node, area, is_focused_node, false, base_styles);
-                    frame.render_widget(Clear, area);
+                    let widget =
+                        self.draw_paragraph(node, area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
                     frame.render_widget(widget, area);
+                    if let Some((scrollbar, bar_area)) = self.draw_scrollbar(node, area) {
+                        frame.render_widget(scrollbar, bar_area);
+                    }
                     true
                 }
                 "tabs" => {
                     let id = format!("{}:index", node.id.clone());
-                    if self.state.get(&id).is_none() {
+                    if !self.state.contains_key(&id) {
                         let mut state = self.state.clone();
                         let thdr = node.children.first();
                         if let Some(wrapped_value) = thdr {
                             let plain_elm = MarkupParser::<B>::extract_element(wrapped_value);
-                            let frst = plain_elm.children.first();
-                            if let Some(first) = frst {
-                                let chld = MarkupParser::<B>::extract_element(first);
-                                state.insert(id, chld.id);
+                            let items: Vec<MarkupElement> = plain_elm
+                                .children
+                                .iter()
+                                .map(MarkupParser::<B>::extract_element)
+                                .collect();
+                            // the default tab, if any, is selected over the first one
+                            let default_tab = extract_attribute(&node.attributes, "default");
+                            let selected = items
+                                .iter()
+                                .find(|item| item.id.eq(&default_tab))
+                                .or(items.first());
+                            if let Some(item) = selected {
+                                state.insert(id, item.id.clone().into());
                             }
                         }
                         self.state = state;
@@ -540,7 +1889,7 @@ impl<B: Backend> MarkupParser<B> {
                 "tab-item" => {
                     let widget =
                         self.draw_tab_item(node, area, is_focused_node, is_active_tab, base_styles);
-                    frame.render_widget(Clear, area);
+                    MarkupParser::<B>::clear_area(frame, node, area);
                     frame.render_widget(widget, area);
                     true
                 }
@@ -548,11 +1897,13 @@ impl<B: Backend> MarkupParser<B> {
                     let default_val = "unknown".to_string();
                     let show_flag = node.attributes.get("tabs-id").unwrap_or(&default_val);
                     let show_flag = format!("{}:index", show_flag);
-                    let state_value = self.state.get(&show_flag).unwrap_or(&default_val);
+                    let state_value = self.state.get(&show_flag).cloned();
+                    let state_value = state_value.unwrap_or(StateValue::from(&default_val));
                     let me = node.attributes.get("for").unwrap_or(&default_val);
                     if state_value.eq(me) {
-                        let widget = self.draw_block(node, area, is_focused_node, false, base_styles);
-                        frame.render_widget(Clear, area);
+                        let widget =
+                            self.draw_block(node, area, is_focused_node, true, base_styles);
+                        MarkupParser::<B>::clear_area(frame, node, area);
                         frame.render_widget(widget, area);
                         return true;
                     }
@@ -560,14 +1911,13 @@ impl<B: Backend> MarkupParser<B> {
                 }
                 "dialog" => {
                     let new_node = node.clone();
-                    let show_flag = extract_attribute(new_node.clone().attributes, "show");
-                    let default_val = "false".to_string();
-                    let state_value = self.state.get(&show_flag).unwrap_or(&default_val);
-                    if state_value.eq(&"true".to_string()) {
+                    let show_flag = extract_attribute(&new_node.attributes, "show");
+                    let state_value = self.state.get(&show_flag).and_then(StateValue::as_bool);
+                    if state_value.unwrap_or(false) {
                         self.add_context(node);
                         let widget =
                             self.draw_dialog(&new_node, area, is_focused_node, false, base_styles);
-                        frame.render_widget(Clear, area);
+                        MarkupParser::<B>::clear_area(frame, node, area);
                         frame.render_widget(widget, area);
                         return true;
                     } else {
@@ -577,107 +1927,219 @@ impl<B: Backend> MarkupParser<B> {
                 }
                 "button" => {
                     let mut new_area = area;
-                    new_area.height = if new_area.height > 3 {
-                        3
+                    // wrapped labels make the button taller, unless it has a height
+                    let rows = if MarkupParser::<B>::has_ellipsis(node) {
+                        1
                     } else {
-                        new_area.height
+                        let text = node.text.clone().unwrap_or_default();
+                        wrap_text(&text, area.width.saturating_sub(2)).len()
                     };
-                    let widget = self.draw_button(node, new_area, is_focused_node, false, base_styles);
-                    frame.render_widget(Clear, area);
+                    let size = extract_attribute(&node.attributes, "height");
+                    let height = MarkupParser::<B>::parse_size(&size, area.height)
+                        .unwrap_or_else(|| u16::try_from(rows + 2).unwrap_or(u16::MAX))
+                        .max(3);
+                    new_area.height = std::cmp::min(new_area.height, height);
+                    let widget =
+                        self.draw_button(node, new_area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
                     frame.render_widget(widget, new_area);
                     true
                 }
-                _ => {
-                    let widget = Block::default();
-                    frame.render_widget(Clear, area);
+                "gauge" => {
+                    let widget = self.draw_gauge(node, area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "progress" => {
+                    // a known progress is a gauge, only the unknown one is animated
+                    if self.get_progress_percent(node).is_some() {
+                        let widget =
+                            self.draw_gauge(node, area, is_focused_node, false, base_styles);
+                        MarkupParser::<B>::clear_area(frame, node, area);
+                        frame.render_widget(widget, area);
+                    } else {
+                        self.animated = true;
+                        let widget =
+                            self.draw_progress(node, area, is_focused_node, false, base_styles);
+                        MarkupParser::<B>::clear_area(frame, node, area);
+                        frame.render_widget(widget, area);
+                    }
+                    true
+                }
+                "sparkline" => {
+                    let data = numbers_from_str(&self.get_bound_value(node).to_string());
+                    let widget =
+                        self.draw_sparkline(node, &data, area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "barchart" => {
+                    let values = labeled_values_from_str(&self.get_bound_value(node).to_string());
+                    let data: Vec<(&str, u64)> = values
+                        .iter()
+                        .map(|(label, value)| (label.as_str(), *value))
+                        .collect();
+                    let widget =
+                        self.draw_barchart(node, &data, area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "divider" => {
+                    let widget = self.draw_divider(node, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "spinner" => {
+                    // only a running spinner needs the ticks to redraw
+                    if self.is_spinning(node) {
+                        self.animated = true;
+                    }
+                    let widget = self.draw_spinner(node, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "menu" => {
+                    let show_flag = extract_attribute(&node.attributes, "show");
+                    let state_value = self.state.get(&show_flag).and_then(StateValue::as_bool);
+                    if state_value.unwrap_or(false) {
+                        self.add_context(node);
+                    } else {
+                        self.remove_context(node);
+                    }
+                    let mut button = node.clone();
+                    button.text = Some(extract_attribute(&node.attributes, "label"));
+                    let widget =
+                        self.draw_button(&button, area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "menu-item" => {
+                    let widget = self.draw_menu_item(node, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "radio-group" => {
+                    let widget =
+                        self.draw_radio_group(node, area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                "skeleton" => {
+                    self.animated = true;
+                    let widget =
+                        self.draw_skeleton(node, area, is_focused_node, false, base_styles);
+                    MarkupParser::<B>::clear_area(frame, node, area);
+                    frame.render_widget(widget, area);
+                    true
+                }
+                _ => {
+                    let widget = Block::default();
+                    MarkupParser::<B>::clear_area(frame, node, area);
                     frame.render_widget(widget, area);
                     true
                 }
             }
         }
-    }
-
-    fn process_block(
-        &self,
-        frame: &mut Frame<B>,
-        node: &MarkupElement,
-        dependency: Option<MarkupElement>,
-        place: Option<Rect>,
-        _margin: Option<u16>, // remove or transform in padding?
-        count: usize,
-    ) -> Vec<(Rect, MarkupElement)> {
-        let current = node.clone();
-        let split_space = place.unwrap_or(frame.size());
-        let border_value = extract_attribute(current.attributes.clone(), "border");
-        let mut res: Vec<(Rect, MarkupElement)> = vec![];
-        let mut constraints: Vec<Constraint> = vec![];
-        let id = extract_attribute(current.attributes.clone(), "id");
-        let mut widgets_info: Vec<(usize, MarkupElement)> = vec![];
-        let mut children_nodes: Vec<(usize, MarkupElement)> = vec![];
-        res.push((place.unwrap_or(frame.size()), current));
-
-        info!(target: "MarkupParser",
-            "{}Container #{}[[{:?}]]",
-            "".repeat(count * 2),
-            id,
-            split_space.clone(),
-        );
-
-        // println!("\n\n==> {}[{:?}]: {:?}\n\n", id.clone(), current.attributes.clone(), split_space.clone());
-
-        for (position, base_child) in node.children.iter().enumerate() {
-            let child = base_child.as_ref().borrow();
-            let constraint = extract_attribute(child.clone().attributes, "constraint");
-            constraints.push(MarkupParser::<B>::get_constraint(constraint));
-            let child_name = child.clone().name;
-
-            if MarkupParser::<B>::is_widget(child_name.as_str()) {
-                widgets_info.push((position, child.clone()));
-            } else {
-                children_nodes.push((position, child.clone()));
-            }
-        }
+    }
+
+    fn process_block(
+        &self,
+        screen: Rect,
+        node: &MarkupElement,
+        dependency: Option<&str>,
+        place: Option<Rect>,
+        _margin: Option<u16>, // remove or transform in padding?
+        count: usize,
+    ) -> Vec<(Rect, MarkupElement)> {
+        let current = node.clone();
+        let split_space = place.unwrap_or(screen);
+        let border_value = extract_attribute(&current.attributes, "border");
+        let mut res: Vec<(Rect, MarkupElement)> = vec![];
+        let id = extract_attribute(&current.attributes, "id");
+        let mut widgets_info: Vec<(usize, MarkupElement)> = vec![];
+        let mut children_nodes: Vec<(usize, MarkupElement)> = vec![];
+        res.push((place.unwrap_or(screen), current));
 
-        let new_margin = if border_value.eq("") || border_value.eq("none") {
+        info!(target: "MarkupParser",
+            "{}Container #{}[[{:?}]]",
+            "".repeat(count * 2),
+            id,
+            split_space.clone(),
+        );
+
+        // println!("\n\n==> {}[{:?}]: {:?}\n\n", id.clone(), current.attributes.clone(), split_space.clone());
+
+        let new_margin = if border_value.is_empty() || border_value.eq("none") {
             0 // margin.unwrap_or(0)
         } else {
             1
         };
+        let available = split_space.width.saturating_sub(new_margin * 2);
+        let children = self.get_rendered_children(node);
+        // an empty container only draws its border and title.
+        if children.is_empty() {
+            return res;
+        }
+
+        for (position, base_child) in children.iter().enumerate() {
+            let child = base_child.as_ref().borrow();
+            if MarkupParser::<B>::is_widget(child.name.as_str()) {
+                widgets_info.push((position, child.clone()));
+            } else {
+                children_nodes.push((position, child.clone()));
+            }
+        }
+
+        let constraints = MarkupParser::<B>::get_constraints(&children, available);
         let layout = Layout::default()
             .direction(Direction::Horizontal)
             .margin(new_margin)
-            .constraints(constraints.clone().as_ref());
+            .constraints(constraints);
+        // `get_constraints` gives every child its own cell
         let chunks = layout.split(split_space);
 
-        for (cntr, base_child) in children_nodes.iter() {
-            let counter = *cntr;
-            let mut child = base_child.clone();
-            if dependency.is_some() {
-                child.dependencies.push(dependency.clone().unwrap().id);
-            }
-            let partial_res = self.process_node(
-                frame,
-                &child,
-                dependency.clone(),
-                Some(chunks[counter]),
-                None,
-                count + 1,
-            );
-            for pair in partial_res.iter() {
-                res.push((pair.0, pair.1.clone()));
+        // dialogs go after the widgets, so they are drawn over them
+        let (dialogs, children_nodes): (Vec<_>, Vec<_>) = children_nodes
+            .into_iter()
+            .partition(|(_, child)| child.name.eq("dialog"));
+
+        for (counter, mut child) in children_nodes.into_iter() {
+            let area = chunks[counter];
+            if let Some(did) = dependency {
+                child.dependencies.push(did.to_string());
             }
+            let partial_res =
+                self.process_node(screen, &child, dependency, Some(area), None, count + 1);
+            res.extend(partial_res);
         }
 
-        for (cntr, widget_info) in widgets_info.iter() {
-            let counter = *cntr;
-            let mut mkp_elm = widget_info.clone();
-            if dependency.is_some() {
-                let did = dependency.clone().unwrap().id;
+        for (counter, mut mkp_elm) in widgets_info.into_iter() {
+            let area = chunks[counter];
+            if let Some(did) = dependency {
+                let did = did.to_string();
                 if !mkp_elm.dependencies.contains(&did) {
                     mkp_elm.dependencies.push(did);
                 }
             }
-            res.push((chunks[counter], mkp_elm));
+            res.push((area, mkp_elm));
+        }
+
+        for (counter, mut dialog) in dialogs.into_iter() {
+            let area = chunks[counter];
+            if let Some(did) = dependency {
+                dialog.dependencies.push(did.to_string());
+            }
+            let partial_res =
+                self.process_node(screen, &dialog, dependency, Some(area), None, count + 1);
+            res.extend(partial_res);
         }
 
         res
@@ -685,69 +2147,135 @@ impl<B: Backend> MarkupParser<B> {
 
     fn process_layout(
         &self,
-        frame: &mut Frame<B>,
+        screen: Rect,
         node: &MarkupElement,
-        dependency: Option<MarkupElement>,
+        dependency: Option<&str>,
         place: Option<Rect>,
         margin: Option<u16>,
         count: usize,
     ) -> Vec<(Rect, MarkupElement)> {
-        let current = node.clone();
-        let split_space = place.unwrap_or(frame.size());
+        let split_space = place.unwrap_or(screen);
         let direction = MarkupParser::<B>::get_direction(node);
-        let id = extract_attribute(current.attributes.clone(), "id");
+        let id = extract_attribute(&node.attributes, "id");
         info!(target: "MarkupParser",
-            "{}Layout #{}[{}]({} children) [[{:?}]]",
+            "{}Layout #{}[{:?}]({} children) [[{:?}]]",
             " ".repeat(count * 2),
             id,
-            current.attributes.get("direction").unwrap(),
+            direction,
             node.children.len(),
             split_space.clone(),
         );
         let mut res: Vec<(Rect, MarkupElement)> = vec![];
-        let constraints: Vec<Constraint> = MarkupParser::<B>::get_constraints(node.clone());
+        let available = match direction {
+            Direction::Vertical => split_space.height,
+            Direction::Horizontal => split_space.width,
+        };
+        let available = available.saturating_sub(margin.unwrap_or(0) * 2);
+        let children = self.get_rendered_children(node);
+        // nothing to split, the layout keeps its place without children areas.
+        if children.is_empty() {
+            return res;
+        }
+        // `constraints` in the layout defines its cells, otherwise every child
+        // has its own one
+        let cells = extract_attribute(&node.attributes, "constraints");
+        let constraints: Vec<Constraint> = if cells.trim().is_empty() {
+            MarkupParser::<B>::get_constraints(&children, available)
+        } else {
+            let cells: Vec<String> = cells
+                .split(',')
+                .map(|cell| cell.trim().to_string())
+                .collect();
+            MarkupParser::<B>::get_fraction_constraints(&cells, available)
+        };
+        let constraints = self.check_percentages(node, constraints);
         info!(target: "MarkupParser", "{}  ::>{:?}", "".repeat(count * 2), constraints);
 
         let layout = Layout::default()
-            .direction(direction)
-            .margin(margin.unwrap_or(0))
-            .constraints(constraints.as_ref());
-
-        let chunks = layout.split(split_space);
+            .direction(direction.clone())
+            .margin(margin.unwrap_or(0));
+        let chunks = layout
+            .clone()
+            .constraints(constraints.clone())
+            .split(split_space);
+        let areas = if cells.trim().is_empty() {
+            match MarkupParser::<B>::get_bounded_constraints(
+                &children,
+                &direction,
+                &constraints,
+                &chunks,
+            ) {
+                // `to_vec` as `ratatui` splits in a `Rc<[Rect]>`
+                Some(constraints) => layout.constraints(constraints).split(split_space).to_vec(),
+                None => chunks.to_vec(),
+            }
+        } else {
+            self.get_spanned_areas(node, &children, &chunks)
+        };
 
-        for (position, base_child) in node.children.iter().enumerate() {
+        for (position, base_child) in children.iter().take(areas.len()).enumerate() {
             let mut child = base_child.as_ref().borrow().clone();
-            if dependency.is_some() {
-                child.dependencies.push(dependency.clone().unwrap().id);
+            if let Some(did) = dependency {
+                child.dependencies.push(did.to_string());
             }
             let partial_res = self.process_node(
-                frame,
+                screen,
                 &child,
-                dependency.clone(),
-                Some(chunks[position]),
+                dependency,
+                Some(areas[position]),
                 Some(1),
                 count + 1,
             );
-            for pair in partial_res.iter() {
-                let mut mkp_elm = pair.1.clone();
-                if dependency.is_some() {
-                    let did = dependency.clone().unwrap().id;
+            for (area, mut mkp_elm) in partial_res {
+                if let Some(did) = dependency {
+                    let did = did.to_string();
                     if !mkp_elm.dependencies.contains(&did) {
                         mkp_elm.dependencies.push(did);
                     }
                 }
-                res.push((pair.0, mkp_elm));
+                res.push((area, mkp_elm));
             }
         }
 
         res
     }
 
+    /// Areas of the children of a layout with `constraints`, a child with
+    /// `span="N"` takes N consecutive cells (up to the last one). Children
+    /// without a cell left are reported as warnings and not drawn.
+    fn get_spanned_areas(
+        &self,
+        node: &MarkupElement,
+        children: &[Rc<RefCell<MarkupElement>>],
+        chunks: &[Rect],
+    ) -> Vec<Rect> {
+        let mut areas = vec![];
+        let mut cell = 0;
+        for base_child in children.iter() {
+            if cell >= chunks.len() {
+                self.add_layout_warning(format!(
+                    "Layout #{} has more children than constraints",
+                    node.id
+                ));
+                break;
+            }
+            let child = base_child.as_ref().borrow();
+            let span = extract_attribute(&child.attributes, "span")
+                .parse::<usize>()
+                .unwrap_or(1)
+                .max(1);
+            let last = std::cmp::min(cell + span, chunks.len()) - 1;
+            areas.push(chunks[cell].union(chunks[last]));
+            cell = last + 1;
+        }
+        areas
+    }
+
     fn process_other(
         &self,
-        frame: &mut Frame<B>,
+        screen: Rect,
         node: &MarkupElement,
-        depends_on: Option<MarkupElement>,
+        depends_on: Option<&str>,
         place: Option<Rect>,
         margin: Option<u16>,
         count: usize,
@@ -766,8 +2294,8 @@ impl<B: Backend> MarkupParser<B> {
                 .clone();
         }
         */
-        let id = extract_attribute(current.attributes.clone(), "id");
-        let mut split_space = place.unwrap_or(frame.size());
+        let id = extract_attribute(&current.attributes, "id");
+        let mut split_space = place.unwrap_or(screen);
         let mut child_space = split_space;
         let mut res: Vec<(Rect, MarkupElement)> = vec![];
         let mut subsequents: Vec<(Rect, MarkupElement)> = vec![];
@@ -784,71 +2312,99 @@ impl<B: Backend> MarkupParser<B> {
         let cname = node.name.as_str();
         match cname {
             "tabs" => {
-                let header_size = 3;
-                let vertical_layout = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(margin.unwrap_or(0))
-                    .constraints(
-                        vec![
-                            Constraint::Length(header_size),
-                            Constraint::Length(split_space.height - header_size),
-                        ]
-                        .as_ref(),
-                    );
-                let vertical_chunks = vertical_layout.split(split_space);
+                let header_size = extract_attribute(&node.attributes, "header-height")
+                    .parse::<u16>()
+                    .unwrap_or(3);
+                let vertical_tabs = MarkupParser::<B>::is_vertical_tabs(node);
+                let column_width = extract_attribute(&node.attributes, "header-width")
+                    .parse::<u16>()
+                    .unwrap_or(12);
+                let tabs_layout = if vertical_tabs {
+                    Layout::default()
+                        .direction(Direction::Horizontal)
+                        .margin(margin.unwrap_or(0))
+                        .constraints(vec![Constraint::Length(column_width), Constraint::Min(0)])
+                } else {
+                    Layout::default()
+                        .direction(Direction::Vertical)
+                        .margin(margin.unwrap_or(0))
+                        .constraints(vec![Constraint::Length(header_size), Constraint::Min(0)])
+                };
+                let vertical_chunks = tabs_layout.split(split_space);
+                // nested tabs are only drawn when the parent content is visible
+                let inherited: Vec<String> = dependency.iter().map(|x| x.to_string()).collect();
                 for (pos, chld) in node.children.iter().enumerate() {
-                    let elm = chld.as_ref().borrow().clone();
                     let child_space = vertical_chunks[pos];
                     if pos > 0 {
+                        let mut elm = chld.as_ref().borrow().clone();
+                        elm.dependencies.extend(inherited.clone());
                         let partial_res = self.process_node(
-                            frame,
+                            screen,
                             &elm,
-                            None,
+                            dependency,
                             Some(child_space),
                             Some(1),
                             count + 1,
                         );
-                        for pair in partial_res.iter() {
-                            subsequents.push(pair.clone());
-                        }
+                        subsequents.extend(partial_res);
                     } else {
-                        let elm = chld.as_ref().borrow().clone();
+                        let elm = chld.as_ref().borrow();
                         let start_x = vertical_chunks[0].x + 1;
                         let start_y = vertical_chunks[0].y;
+                        let orientation = extract_attribute(&node.attributes, "orientation");
                         let line = MarkupElement {
                             id: "line_unk".to_string(),
-                            attributes: HashMap::new(),
+                            attributes: HashMap::from([(
+                                "orientation".to_string(),
+                                orientation.clone(),
+                            )]),
                             parent_node: None,
                             children: vec![],
                             name: "tabs-borders".to_string(),
                             text: None,
                             deep: 0,
-                            dependencies: vec![],
+                            dependencies: inherited.clone(),
                             order: -1,
                         };
                         let tab_width: u16 = 8;
                         subsequents.push((vertical_chunks[0], line));
                         for (_idx, chld) in elm.children.iter().enumerate() {
                             let idx: u16 = _idx as u16;
-                            let chldelm = chld.as_ref().clone().into_inner();
+                            let chldelm = chld.as_ref().borrow();
                             let order = 10 + (idx as i32);
+                            let mut attributes = chldelm.attributes.clone();
+                            attributes.insert("orientation".to_string(), orientation.clone());
                             let btn = MarkupElement {
                                 id: chldelm.id.clone(),
-                                attributes: chldelm.attributes.clone(),
+                                attributes,
                                 parent_node: elm.parent_node.clone(),
                                 children: vec![],
-                                name: chldelm.name,
+                                name: chldelm.name.clone(),
                                 text: chldelm.text.clone(),
                                 deep: chldelm.deep + 1,
-                                dependencies: vec![],
+                                dependencies: inherited.clone(),
                                 order,
                             };
-                            let place = Rect::new(
-                                start_x + (idx * tab_width) + (idx),
-                                start_y,
-                                tab_width + 1,
-                                2,
-                            );
+                            let place = if vertical_tabs {
+                                let item_height = 3;
+                                let place = Rect::new(
+                                    vertical_chunks[0].x,
+                                    start_y + idx * item_height,
+                                    column_width.saturating_sub(1),
+                                    item_height,
+                                );
+                                clip_rect(place, vertical_chunks[0])
+                            } else {
+                                Rect::new(
+                                    start_x + (idx * tab_width) + (idx),
+                                    start_y,
+                                    tab_width + 1,
+                                    2,
+                                )
+                            };
+                            if place.area() == 0 {
+                                continue;
+                            }
                             subsequents.push((place, btn));
                         }
                     }
@@ -856,41 +2412,41 @@ impl<B: Backend> MarkupParser<B> {
                 process_children = false;
             }
             "tab-content" => {
+                // the header is drawn by the tabs, the content fills its place
                 let vertical_layout = Layout::default()
                     .direction(Direction::Vertical)
                     .margin(margin.unwrap_or(0))
-                    .constraints(
-                        vec![Constraint::Percentage(10), Constraint::Percentage(90)].as_ref(),
-                    );
-                let vertical_chunks = vertical_layout.split(split_space);
-                split_space = vertical_chunks[1];
-                dependency = Some(node.clone());
+                    .constraints(vec![Constraint::Min(0)]);
+                split_space = vertical_layout.split(split_space)[0];
+                dependency = Some(node.id.as_str());
             }
             "dialog" => {
+                let outer_margin = margin.unwrap_or(0);
+                let width = extract_attribute(&node.attributes, "width");
                 let horizontal_layout = Layout::default()
                     .direction(Direction::Horizontal)
-                    .margin(margin.unwrap_or(0))
-                    .constraints(
-                        vec![
-                            Constraint::Percentage(34),
-                            Constraint::Percentage(32),
-                            Constraint::Percentage(34),
-                        ]
-                        .as_ref(),
-                    );
-                let horizontal_chunks = horizontal_layout.split(frame.size());
+                    .margin(outer_margin)
+                    .constraints(MarkupParser::<B>::get_dialog_constraints(
+                        width.clone(),
+                        screen.width.saturating_sub(outer_margin * 2),
+                        [34, 32, 34],
+                    ));
+                let horizontal_chunks = horizontal_layout.split(screen);
 
-                let vertical_layout = Layout::default()
+                let vertical_constraints = MarkupParser::<B>::get_dialog_constraints(
+                    extract_attribute(&node.attributes, "height"),
+                    horizontal_chunks[1].height.saturating_sub(outer_margin * 2),
+                    [31, 34, 31],
+                );
+                // an explicit width is not reduced by the vertical margin
+                let vertical_layout = if width.is_empty() {
+                    Layout::default().margin(outer_margin)
+                } else {
+                    Layout::default().vertical_margin(outer_margin)
+                };
+                let vertical_layout = vertical_layout
                     .direction(Direction::Vertical)
-                    .margin(margin.unwrap_or(0))
-                    .constraints(
-                        vec![
-                            Constraint::Percentage(31),
-                            Constraint::Percentage(34),
-                            Constraint::Percentage(31),
-                        ]
-                        .as_ref(),
-                    );
+                    .constraints(vertical_constraints);
                 let vertical_chunks = vertical_layout.split(horizontal_chunks[1]);
 
                 split_space = vertical_chunks[1];
@@ -899,14 +2455,18 @@ impl<B: Backend> MarkupParser<B> {
                 let dialog_parts = Layout::default()
                     .direction(Direction::Vertical)
                     .margin(1)
-                    .constraints(
-                        vec![Constraint::Percentage(80), Constraint::Percentage(20)].as_ref(),
-                    );
+                    .constraints(vec![Constraint::Percentage(80), Constraint::Percentage(20)]);
                 let dialog_chunks = dialog_parts.split(dialog_space);
 
-                let action = extract_attribute(node.attributes.clone(), "action");
-                let btns = extract_attribute(node.attributes.clone(), "buttons");
+                let action = extract_attribute(&node.attributes, "action");
+                let show_flag = extract_attribute(&node.attributes, "show");
+                let btns = extract_attribute(&node.attributes, "buttons");
                 let btns: Vec<String> = btns.split('|').map(String::from).collect();
+                let btn_actions = extract_attribute(&node.attributes, "actions");
+                let btn_actions: Vec<String> = btn_actions
+                    .split('|')
+                    .map(|x| x.trim().to_string())
+                    .collect();
                 let btn_constraints: Vec<Constraint> = btns
                     .clone()
                     .iter()
@@ -915,13 +2475,16 @@ impl<B: Backend> MarkupParser<B> {
 
                 let buttons_layout = Layout::default()
                     .direction(Direction::Horizontal)
-                    .constraints(btn_constraints.as_ref());
+                    .constraints(btn_constraints);
                 child_space = dialog_chunks[0];
                 let button_chunks = buttons_layout.split(dialog_chunks[1]);
 
                 for (elm_idx, btn) in btns.iter().enumerate() {
                     let btn_id = format!("{}_btn_{}", node.id, btn);
-                    let btn_action = if !action.is_empty() {
+                    let explicit_action = btn_actions.get(elm_idx).filter(|x| !x.is_empty());
+                    let btn_action = if let Some(explicit_action) = explicit_action {
+                        explicit_action.clone()
+                    } else if !action.is_empty() {
                         action.clone()
                     } else {
                         format!("on_{}", btn_id)
@@ -936,6 +2499,7 @@ impl<B: Backend> MarkupParser<B> {
                             ("id".to_string(), btn_id.clone()),
                             ("action".to_string(), btn_action),
                             ("index".to_string(), format!("{}", elm_idx)),
+                            ("dialog-show".to_string(), show_flag.clone()),
                         ]),
                         children: vec![],
                         parent_node: Some(Rc::new(RefCell::new(node.clone()))),
@@ -945,14 +2509,74 @@ impl<B: Backend> MarkupParser<B> {
                     current.children.push(btn_desc);
                     subsequents.push((button_chunks[elm_idx], btn_elm));
                 }
-                dependency = Some(node.clone());
+                dependency = Some(node.id.as_str());
+            }
+            // a divider fills its (usually one cell wide) place, without margin
+            "divider" => {
+                process_children = false;
+            }
+            // the items of an open menu are placed below it, over the elements
+            // drawn there
+            "menu" => {
+                process_children = false;
+                let layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .margin(margin.unwrap_or(0))
+                    .constraints(vec![Constraint::Percentage(100)]);
+                split_space = layout.split(place.unwrap_or(screen))[0];
+                let show_flag = extract_attribute(&node.attributes, "show");
+                let state_value = self.state.get(&show_flag).and_then(StateValue::as_bool);
+                current.children = vec![];
+                if state_value.unwrap_or(false) {
+                    let items: Vec<MarkupElement> = node
+                        .children
+                        .iter()
+                        .map(|child| child.as_ref().borrow().clone())
+                        .filter(|child| child.name.eq("menu-item") && self.is_rendered(child))
+                        .collect();
+                    let width = items
+                        .iter()
+                        .map(|item| item.text.clone().unwrap_or_default().width() as u16 + 2)
+                        .max()
+                        .unwrap_or(0)
+                        .max(split_space.width)
+                        .min(screen.right().saturating_sub(split_space.x));
+                    for (position, mut item) in items.into_iter().enumerate() {
+                        let y = split_space.bottom() + position as u16;
+                        if y >= screen.bottom() {
+                            break;
+                        }
+                        item.order = position as i32;
+                        item.dependencies.push(node.id.clone());
+                        current.children.push(Rc::new(RefCell::new(item.clone())));
+                        subsequents.push((Rect::new(split_space.x, y, width, 1), item));
+                    }
+                }
+            }
+            // the inline elements are drawn by their paragraph
+            "p" => {
+                process_children = false;
+                let layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .margin(margin.unwrap_or(0))
+                    .constraints(vec![Constraint::Percentage(100)]);
+                split_space = layout.split(place.unwrap_or(screen))[0];
+            }
+            // the radios are drawn by their group
+            "radio-group" => {
+                process_children = false;
+                let layout = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .margin(margin.unwrap_or(0))
+                    .constraints(vec![Constraint::Percentage(100)]);
+                split_space = layout.split(place.unwrap_or(screen))[0];
             }
             _ => {
                 let layout = Layout::default()
                     .direction(Direction::Horizontal)
                     .margin(margin.unwrap_or(0))
-                    .constraints(vec![Constraint::Percentage(100)].as_ref());
-                split_space = layout.split(place.unwrap_or(frame.size()))[0];
+                    .constraints(vec![Constraint::Percentage(100)]);
+                split_space = layout.split(place.unwrap_or(screen))[0];
             }
         }
         res.push((split_space, current));
@@ -960,26 +2584,25 @@ impl<B: Backend> MarkupParser<B> {
         if process_children {
             for base_child in node.children.iter() {
                 let mut child = base_child.as_ref().borrow().clone();
-                if dependency.is_some() {
-                    child.dependencies.push(dependency.clone().unwrap().id);
+                if let Some(did) = dependency {
+                    child.dependencies.push(did.to_string());
                 }
                 let partial_res = self.process_node(
-                    frame,
+                    screen,
                     &child,
-                    dependency.clone(),
+                    dependency,
                     Some(child_space),
                     Some(1),
                     count + 1,
                 );
-                for pair in partial_res.iter() {
-                    let mut mkp_elm = pair.1.clone();
-                    if dependency.is_some() {
-                        let did = dependency.clone().unwrap().id;
+                for (area, mut mkp_elm) in partial_res {
+                    if let Some(did) = dependency {
+                        let did = did.to_string();
                         if !mkp_elm.dependencies.contains(&did) {
                             mkp_elm.dependencies.push(did);
                         }
                     }
-                    res.push((pair.0, mkp_elm));
+                    res.push((area, mkp_elm));
                 }
             }
         }
@@ -987,162 +2610,984 @@ impl<B: Backend> MarkupParser<B> {
         for shld in subsequents {
             res.push(shld);
         }
-
-        Some(res)
+
+        Some(res)
+    }
+
+    /// Evaluates the `if` and `if-not` attributes of an element, they name a
+    /// state key that must (or must not) be "true" for the element to exist.
+    fn is_rendered(&self, node: &MarkupElement) -> bool {
+        let is_true = |key: &str| {
+            self.state
+                .get(key)
+                .and_then(StateValue::as_bool)
+                .unwrap_or(false)
+        };
+        let condition = extract_attribute(&node.attributes, "if");
+        let negation = extract_attribute(&node.attributes, "if-not");
+        (condition.is_empty() || is_true(&condition))
+            && (negation.is_empty() || !is_true(&negation))
+    }
+
+    /// Children of an element skipping the ones hidden by `if`/`if-not`, so
+    /// they don't take space in the layout.
+    fn get_rendered_children(&self, node: &MarkupElement) -> Vec<Rc<RefCell<MarkupElement>>> {
+        node.children
+            .iter()
+            .filter(|child| self.is_rendered(&child.as_ref().borrow()))
+            .cloned()
+            .collect()
+    }
+
+    /// Expands the template (first child) of a `repeat` element once per item
+    /// of the state list referenced by `for`, and lays out the copies like a
+    /// layout (vertical by default).
+    fn process_repeat(
+        &self,
+        screen: Rect,
+        node: &MarkupElement,
+        dependency: Option<&str>,
+        place: Option<Rect>,
+        margin: Option<u16>,
+        count: usize,
+    ) -> Vec<(Rect, MarkupElement)> {
+        let template = match node.children.first() {
+            Some(template) => template,
+            None => return vec![],
+        };
+        let list = self
+            .state
+            .get(&extract_attribute(&node.attributes, "for"))
+            .cloned()
+            .unwrap_or_default();
+        let separator = extract_attribute(&node.attributes, "separator");
+        let separator = if separator.is_empty() {
+            ","
+        } else {
+            separator.as_str()
+        };
+        let variable = extract_attribute(&node.attributes, "as");
+        let variable = if variable.is_empty() {
+            "item"
+        } else {
+            variable.as_str()
+        };
+        let placeholder = format!("{{{{{}}}}}", variable);
+
+        let mut layout = node.clone();
+        layout.name = "layout".to_string();
+        if !layout.attributes.contains_key("direction") {
+            layout
+                .attributes
+                .insert("direction".to_string(), "vertical".to_string());
+        }
+        layout.children = list
+            .as_list(separator)
+            .iter()
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .enumerate()
+            .map(|(idx, item)| {
+                let instance = MarkupParser::<B>::instantiate(
+                    template,
+                    node.parent_node.clone(),
+                    &format!("_{}", idx),
+                    &placeholder,
+                    item,
+                );
+                let id = format!("{}_{}", node.id, idx);
+                let mut root = instance.as_ref().borrow_mut();
+                root.attributes.insert("id".to_string(), id.clone());
+                root.id = id;
+                drop(root);
+                instance
+            })
+            .collect();
+        if layout.children.is_empty() {
+            return vec![];
+        }
+        self.process_layout(screen, &layout, dependency, place, margin, count)
+    }
+
+    /// Deep copy of a template subtree, the ids get a suffix and the
+    /// placeholder is replaced by the value in texts and attributes.
+    fn instantiate(
+        template: &Rc<RefCell<MarkupElement>>,
+        parent: Option<Rc<RefCell<MarkupElement>>>,
+        suffix: &str,
+        placeholder: &str,
+        value: &str,
+    ) -> Rc<RefCell<MarkupElement>> {
+        let source = template.as_ref().borrow();
+        let id = format!("{}{}", source.id, suffix);
+        let mut attributes: HashMap<String, String> = source
+            .attributes
+            .iter()
+            .map(|(key, attr)| (key.clone(), attr.replace(placeholder, value)))
+            .collect();
+        if attributes.contains_key("id") {
+            attributes.insert("id".to_string(), id.clone());
+        }
+        let instance = Rc::new(RefCell::new(MarkupElement {
+            deep: source.deep,
+            id,
+            text: source.text.as_ref().map(|x| x.replace(placeholder, value)),
+            order: -1,
+            name: source.name.clone(),
+            attributes,
+            children: vec![],
+            parent_node: parent,
+            dependencies: source.dependencies.clone(),
+        }));
+        let children = source
+            .children
+            .iter()
+            .map(|child| {
+                MarkupParser::<B>::instantiate(
+                    child,
+                    Some(instance.clone()),
+                    suffix,
+                    placeholder,
+                    value,
+                )
+            })
+            .collect();
+        instance.as_ref().borrow_mut().children = children;
+        instance
+    }
+
+    fn process_node(
+        &self,
+        screen: Rect,
+        node: &MarkupElement,
+        depends_on: Option<&str>,
+        place: Option<Rect>,
+        margin: Option<u16>,
+        count: usize,
+    ) -> Vec<(Rect, MarkupElement)> {
+        if !self.is_rendered(node) {
+            return vec![];
+        }
+        let name = node.name.as_str();
+        let values: Vec<(Rect, MarkupElement)> = match name {
+            "styles" | "keybindings" => vec![],
+            "layout" => self.process_layout(screen, node, depends_on, place, margin, count),
+            "repeat" => self.process_repeat(screen, node, depends_on, place, margin, count),
+            "container" => self.process_block(screen, node, depends_on, place, margin, count),
+            "block" => self.process_block(screen, node, depends_on, place, margin, count),
+            _ => {
+                let res = self.process_other(screen, node, depends_on, place, margin, count);
+                if let Some(value) = res {
+                    value
+                } else {
+                    warn!("Unknown node type \"{}\"", name);
+                    vec![]
+                }
+            }
+        };
+        values
+    }
+
+    /// Unknown element and attribute names of a node (typos like
+    /// `<containr>` would be drawn as empty blocks) and unknown directions.
+    /// Elements registered in the renderer storage are known.
+    fn check_names(
+        name: &str,
+        attrs: &HashMap<String, String>,
+        storage: &RendererStorage<B>,
+    ) -> Vec<String> {
+        let mut warnings = vec![];
+        if !ELEMENT_NAMES.contains(&name) && !storage.has_component(name) {
+            warnings.push(format!("Unknown element <{}>", name));
+        }
+        let mut unknown: Vec<&String> = attrs
+            .keys()
+            .filter(|attr| !ATTRIBUTE_NAMES.contains(&attr.as_str()) && !attr.starts_with("param-"))
+            .collect();
+        unknown.sort();
+        for attr in unknown {
+            warnings.push(format!("Unknown attribute \"{}\" in <{}>", attr, name));
+        }
+        if let Some(direction) = attrs.get("direction") {
+            if !DIRECTION_NAMES.contains(&direction.trim().to_lowercase().as_str()) {
+                warnings.push(format!("Unknown direction \"{}\" in <{}>", direction, name));
+            }
+        }
+        for warning in warnings.iter() {
+            warn!("{}", warning);
+        }
+        warnings
+    }
+
+    /// Unknown element and attribute names found parsing the markup, followed
+    /// by the layout problems (e.g. percentages over 100%) found rendering it.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.warnings.clone();
+        warnings.extend(self.layout_warnings.borrow().iter().cloned());
+        warnings
+    }
+
+    /// Time between the ticks of `ui_loop` (animations and redraws), 200ms by
+    /// default.
+    pub fn set_tick_rate(&mut self, tick_rate: Duration) -> &mut Self {
+        self.tick_rate = tick_rate;
+        self
+    }
+
+    /// Handles the repeated key events sent while a key is held (terminals
+    /// reporting them), enabled by default.
+    pub fn set_key_repeat(&mut self, key_repeat: bool) -> &mut Self {
+        self.key_repeat = key_repeat;
+        self
+    }
+
+    /// Runs `ui_loop` in the alternate screen, so the previous contents of the
+    /// terminal are back on exit, enabled by default.
+    pub fn set_alternate_screen(&mut self, alternate_screen: bool) -> &mut Self {
+        self.alternate_screen = alternate_screen;
+        self
+    }
+
+    /// Tells if `ui_loop` runs in the alternate screen.
+    pub fn uses_alternate_screen(&self) -> bool {
+        self.alternate_screen
+    }
+
+    /// In strict mode the unknown element and attribute names mark the parser
+    /// as failed.
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        let strict_failure = matches!(self.markup_error, Some(MarkupError::Strict(_)));
+        if strict && !self.failed && !self.warnings.is_empty() {
+            let error = MarkupError::Strict(self.warnings.clone());
+            self.failed = true;
+            self.error = Some(error.to_string());
+            self.markup_error = Some(error);
+        } else if !strict && strict_failure {
+            self.failed = false;
+            self.error = None;
+            self.markup_error = None;
+        }
+        self
+    }
+
+    /// Registers an action, replacing the action with the same name. The
+    /// built-in actions can be replaced too:
+    ///
+    /// - `__change_tab`: selects the tab of a `tab-item` (Enter or number keys).
+    /// - `__close_dialog`: hides the dialog owning a button, closes menus too.
+    /// - `__open_menu`: opens a `menu`.
+    ///
+    pub fn add_action(&mut self, name: &str, action: ActionCallback) -> &mut Self {
+        self.actions.add_action(String::from(name), action);
+        self
+    }
+
+    /// Registers a callback for the keys not consumed by the focus navigation
+    /// or the focused widgets. Unlike the `ui_loop` *on_event* callback it
+    /// doesn't receive the keys already handled.
+    pub fn on_unhandled_key(
+        &mut self,
+        callback: impl FnMut(KeyEvent, &mut HashMap<String, StateValue>) -> EventResponse + 'static,
+    ) -> &mut Self {
+        self.unhandled_key_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Registers a callback called with the previous and the new focused ids
+    /// (`None` when nothing is focused) when Tab or the arrows move the focus.
+    /// It can update the state, e.g. to show the details of the focused item.
+    pub fn set_on_focus_change(
+        &mut self,
+        callback: impl FnMut(Option<&str>, Option<&str>, &mut HashMap<String, StateValue>) + 'static,
+    ) -> &mut Self {
+        self.focus_change_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Registers a callback called on every tick of the event loop (see
+    /// `set_tick_rate`), e.g. to poll a channel or update a clock. State
+    /// changes are drawn on the next loop.
+    pub fn set_on_tick(
+        &mut self,
+        callback: impl FnMut(&mut MarkupParser<B>) + 'static,
+    ) -> &mut Self {
+        self.tick_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// An element can be drawn when all the elements it depends on (e.g. the
+    /// parent tab-content of nested tabs) were drawn.
+    fn can_be_drawn(&self, node: MarkupElement, drawn: &[String]) -> bool {
+        node.dependencies.iter().all(|eid| drawn.contains(eid))
+    }
+
+    /// Fingerprint of the structure of the UI (focus ring, open dialogs and
+    /// the state values that change the tree, like the `repeat` lists), the
+    /// layout pass only depends on it and on the frame size.
+    fn get_structure_fingerprint(&self) -> String {
+        let idxd: Vec<String> = self.indexed_elements.iter().map(|x| x.id.clone()).collect();
+        let mut fngrprnt = format!("{}:{}:", self.contexts.len(), idxd.join("~"));
+        for key in self.layout_state_keys.iter() {
+            let value = self.state.get(key).cloned().unwrap_or_default();
+            fngrprnt = format!("{}-{}_{}", fngrprnt, key, value);
+        }
+        fngrprnt
+    }
+
+    /// The render is cached in two levels:
+    /// - this fingerprint (structure, focus, state and animation ticks) tells
+    ///   `ui_loop` if the frame must be drawn again at all.
+    /// - the layout pass (`process_node`) is cached by the structure
+    ///   fingerprint and the frame size (see `get_drawables`), so state changes
+    ///   (e.g. typing or switching tabs) only draw the elements again without
+    ///   computing the layout of the whole tree.
+    fn get_fingerprint(&self) -> String {
+        let mut state_fngrprnt = format!("{}:{}", self.current, self.get_structure_fingerprint());
+        for (key, value) in self.state.clone().iter() {
+            state_fngrprnt = format!("{}-{}_{}", state_fngrprnt, key, value);
+        }
+        if self.animated {
+            state_fngrprnt = format!("{}:{}", state_fngrprnt, self.ticks);
+        }
+        state_fngrprnt
+    }
+
+    fn update_fingerprint(&mut self) {
+        let state_fngrprnt = self.get_fingerprint();
+        self.fingerprint = state_fngrprnt;
+    }
+
+    /// Returns the elements to draw with their areas, running the layout pass
+    /// only when the structure or the root area changed since the last call.
+    fn get_drawables(&mut self, area: Rect, root: &MarkupElement) -> Vec<(Rect, MarkupElement)> {
+        let layout_fingerprint = format!("{:?}:{}", area, self.get_structure_fingerprint());
+        if !layout_fingerprint.eq(&self.layout_fingerprint) {
+            self.layout_cache = self.process_node(area, root, None, None, None, 0);
+            self.layout_fingerprint = layout_fingerprint;
+        }
+        std::mem::take(&mut self.layout_cache)
+    }
+
+    /// Draws one frame for apps running their own loop: call it inside their
+    /// `terminal.draw(...)` and send the keys to `handle_key` (and the mouse
+    /// events to `handle_mouse`); `needs_redraw` tells if the UI changed since
+    /// the last frame.
+    ///
+    /// ```
+    /// use tui_markup_renderer::{
+    ///     backend::{backend::TestBackend, Terminal},
+    ///     markup_parser::MarkupParser,
+    /// };
+    /// use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    ///
+    /// let path = "tests/assets/sample_buttons.tml".to_string();
+    /// let mut parser = MarkupParser::<TestBackend>::new(path, None, None);
+    /// let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+    /// terminal.draw(|frame| parser.draw_into(frame).unwrap()).unwrap();
+    /// assert!(!parser.needs_redraw());
+    ///
+    /// parser.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+    /// assert!(parser.needs_redraw());
+    /// ```
+    pub fn draw_into(&mut self, frame: &mut Frame<B>) -> Result<(), String> {
+        self.render_ui(frame)?;
+        self.update_fingerprint();
+        Ok(())
+    }
+
+    /// Tells if the state, the focus or the tree changed since the last frame
+    /// drawn by `draw_into` (or the built-in loop).
+    pub fn needs_redraw(&self) -> bool {
+        !self.get_fingerprint().eq(&self.fingerprint)
+    }
+
+    /// Render the current state of the tree
+    ///
+    pub fn render_ui(&mut self, frame: &mut Frame<B>) -> Result<bool, String> {
+        let area = frame.size();
+        self.render_ui_in(frame, area)
+    }
+
+    /// Same as `render_ui` but the tree takes the given `root_area` instead of
+    /// the whole frame (e.g. a chunk of a bigger `tui` layout), the dialogs are
+    /// centered in it too.
+    ///
+    pub fn render_ui_in(&mut self, frame: &mut Frame<B>, root_area: Rect) -> Result<bool, String> {
+        let elm = self.root.clone();
+        if elm.is_some() {
+            let root = MarkupParser::<B>::get_element(elm);
+            if let Some((widget, area)) = self.draw_too_small(&root, root_area) {
+                frame.render_widget(widget, area);
+                self.layout_cache = vec![];
+                self.layout_fingerprint = String::new();
+                self.visible_ids = vec![];
+                return Ok(true);
+            }
+            let mut drawables = self.get_drawables(root_area, &root);
+            // open menus are drawn over the rest of the elements
+            drawables.sort_by_key(|(_, node)| node.name.eq("menu-item"));
+            self.animated = false;
+            let mut drawn: Vec<String> = vec![];
+            drawables.iter().for_each(|pair| {
+                // areas out of the root area (e.g. on a tiny terminal) are clipped
+                let area = clip_rect(pair.0, root_area);
+                let node = pair.1.clone();
+                if self.can_be_drawn(node.clone(), &drawn) {
+                    // println!("{} can be drawn...", &node.id);
+                    let done = self.draw_element(frame, area, &node);
+                    if done {
+                        drawn.push(node.id);
+                    }
+                } else {
+                    // println!("{} cant be drawn...", &node.id);
+                }
+            });
+            if drawn.is_empty() {
+                let (widget, area) = MarkupParser::<B>::draw_no_widgets(root_area);
+                frame.render_widget(widget, area);
+            }
+            self.layout_cache = drawables;
+            self.visible_ids = drawn;
+            Ok(true)
+        } else {
+            let err = "Critical error on render process.".to_string();
+            Err(err)
+        }
+    }
+
+    /// Adds a new tab at the end of the tabs component `tabs_id`, with an
+    /// empty `tab-content` linked to it. Returns false if the tabs component
+    /// doesn't exist or the tab is already there.
+    pub fn add_tab(&mut self, tabs_id: &str, tab_id: &str, title: &str) -> bool {
+        let tabs = self.find_node(tabs_id);
+        let tabs = if let Some(tabs) = tabs {
+            MarkupParser::<B>::extract_element(&tabs)
+        } else {
+            return false;
+        };
+        if tabs.children.len() < 2 || self.find_node(tab_id).is_some() {
+            return false;
+        }
+        let header = tabs.children[0].clone();
+        let body = tabs.children[1].clone();
+
+        let header_elm = MarkupParser::<B>::extract_element(&header);
+        let order = header_elm
+            .children
+            .iter()
+            .map(|x| x.as_ref().borrow().order)
+            .max()
+            .unwrap_or(header_elm.order * 10)
+            + 1;
+        let item = MarkupElement {
+            deep: header_elm.deep + 1,
+            id: tab_id.to_string(),
+            name: "tab-item".to_string(),
+            order,
+            text: Some(title.to_string()),
+            attributes: HashMap::from([
+                ("id".to_string(), tab_id.to_string()),
+                ("action".to_string(), "__change_tab".to_string()),
+                ("index".to_string(), format!("{}", order)),
+                ("tabs-id".to_string(), tabs_id.to_string()),
+            ]),
+            children: vec![],
+            parent_node: Some(header.clone()),
+            dependencies: vec![],
+        };
+        header
+            .as_ref()
+            .borrow_mut()
+            .children
+            .push(Rc::new(RefCell::new(item)));
+
+        let content_id = format!("{}_content", tab_id);
+        let content = MarkupElement {
+            deep: body.as_ref().borrow().deep + 1,
+            id: content_id.clone(),
+            name: "tab-content".to_string(),
+            order: -1,
+            text: None,
+            attributes: HashMap::from([
+                ("id".to_string(), content_id),
+                ("for".to_string(), tab_id.to_string()),
+                ("tabs-id".to_string(), tabs_id.to_string()),
+            ]),
+            children: vec![],
+            parent_node: Some(body.clone()),
+            dependencies: vec![],
+        };
+        body.as_ref()
+            .borrow_mut()
+            .children
+            .push(Rc::new(RefCell::new(content)));
+
+        self.reindex();
+        true
+    }
+
+    /// Removes the tab `tab_id` (and its `tab-content`) from the tabs component
+    /// `tabs_id`. When the removed tab is the active one a neighbor tab gets
+    /// active. Returns false if the tab doesn't exist.
+    pub fn remove_tab(&mut self, tabs_id: &str, tab_id: &str) -> bool {
+        let tabs = self.find_node(tabs_id);
+        let tabs = if let Some(tabs) = tabs {
+            MarkupParser::<B>::extract_element(&tabs)
+        } else {
+            return false;
+        };
+        if tabs.children.len() < 2 {
+            return false;
+        }
+        let header = tabs.children[0].clone();
+        let body = tabs.children[1].clone();
+
+        let position = header
+            .as_ref()
+            .borrow()
+            .children
+            .iter()
+            .position(|x| x.as_ref().borrow().id.eq(tab_id));
+        let position = if let Some(position) = position {
+            position
+        } else {
+            return false;
+        };
+        header.as_ref().borrow_mut().children.remove(position);
+        body.as_ref().borrow_mut().children.retain(|x| {
+            let content = x.as_ref().borrow();
+            !extract_attribute(&content.attributes, "for").eq(tab_id)
+        });
+
+        let key = format!("{}:index", tabs_id);
+        if self.state.get(&key).map(|x| x.eq(tab_id)).unwrap_or(false) {
+            let header = header.as_ref().borrow();
+            let neighbor = header
+                .children
+                .get(position)
+                .or_else(|| header.children.last());
+            if let Some(neighbor) = neighbor {
+                self.state
+                    .insert(key, neighbor.as_ref().borrow().id.clone().into());
+            } else {
+                self.state.remove(&key);
+            }
+        }
+
+        self.reindex();
+        true
+    }
+
+    /// Rebuilds the focusable elements list from the parsed tree.
+    fn reindex(&mut self) {
+        fn collect(node: &Rc<RefCell<MarkupElement>>, res: &mut Vec<MarkupElement>) {
+            let elm = node.as_ref().borrow();
+            if elm.order != -1 {
+                res.push(elm.clone());
+            }
+            elm.children.iter().for_each(|child| collect(child, res));
+        }
+        let mut indexed_elements = vec![];
+        if let Some(root) = self.root.as_ref() {
+            collect(root, &mut indexed_elements);
+        }
+        indexed_elements.retain(MarkupParser::<B>::is_focusable);
+        indexed_elements.sort_by_key(|e| e.order);
+        if self.contexts.is_empty() {
+            self.indexed_elements = indexed_elements;
+        } else {
+            self.contexts[0].1 = indexed_elements;
+        }
+        if self.current > self.last_index() {
+            self.current = -1;
+        }
+        // the tree changed, the layout must be computed again
+        self.layout_fingerprint = String::from("<>");
+        self.fingerprint = String::from("<>");
+    }
+
+    /// Opens the focus context of a dialog: its buttons become the focusable
+    /// elements until `remove_context` and the button named by its `default`
+    /// attribute (or the first one) gets the focus. Opening an already open
+    /// dialog does nothing.
+    pub fn add_context(&mut self, node: &MarkupElement) {
+        if self.contexts.iter().any(|ctx| ctx.0.eq(&node.id)) {
+            return;
+        }
+        self.contexts
+            .push((node.id.clone(), self.indexed_elements.clone()));
+        let chld: Vec<MarkupElement> = node
+            .clone()
+            .children
+            .iter()
+            .map(|x| x.as_ref().borrow().clone())
+            .filter(MarkupParser::<B>::is_focusable)
+            .collect();
+        let default_btn = extract_attribute(&node.attributes, "default");
+        let default_idx = chld
+            .iter()
+            .position(|x| x.name.eq("button") && x.text.as_deref() == Some(default_btn.as_str()));
+        self.current = match default_idx {
+            Some(idx) => idx as i32,
+            None if chld.is_empty() => -1,
+            None => 0,
+        };
+        self.indexed_elements = chld;
+        self.fingerprint = String::from("<>");
+    }
+
+    /// Closes the focus context of a dialog. When the dialog isn't the top of
+    /// the stack (e.g. a dialog closed under another one) the context above
+    /// it restores the elements the dialog saved.
+    pub fn remove_context(&mut self, node: &MarkupElement) {
+        let position = self.contexts.iter().position(|ctx| ctx.0.eq(&node.id));
+        if let Some(position) = position {
+            let (_, saved) = self.contexts.remove(position);
+            if let Some(above) = self.contexts.get_mut(position) {
+                above.1 = saved;
+            } else {
+                self.indexed_elements = saved;
+                self.current = -1;
+            }
+            self.fingerprint = String::from("<>");
+        }
+    }
+
+    /// Sets a UI state value, the next loop iteration redraws the UI.
+    ///
+    /// ```
+    /// use tui_markup_renderer::{backend::backend::TestBackend, markup_parser::MarkupParser};
+    ///
+    /// let path = "tests/assets/sample_gauge.tml".to_string();
+    /// let mut parser = MarkupParser::<TestBackend>::new(path, None, None);
+    /// parser.set_state("download.percent", "50");
+    /// assert_eq!(parser.render_plain(20, 3).trim(), "Down 50%");
+    ///
+    /// parser.set_state("download.percent", "75");
+    /// assert_eq!(parser.get_state("download.percent").unwrap(), "75");
+    /// assert_eq!(parser.render_plain(20, 3).trim(), "Down 75%");
+    /// ```
+    pub fn set_state(&mut self, key: &str, value: impl Into<StateValue>) -> &mut Self {
+        self.state.insert(key.to_string(), value.into());
+        self.fingerprint = String::from("<>");
+        self
+    }
+
+    pub fn get_state(&self, key: &str) -> Option<&StateValue> {
+        self.state.get(key)
+    }
+
+    /// Removes a UI state value and returns it, the next loop iteration
+    /// redraws the UI.
+    pub fn remove_state(&mut self, key: &str) -> Option<StateValue> {
+        self.fingerprint = String::from("<>");
+        self.state.remove(key)
+    }
+
+    /// Removes all the UI state values, the next loop iteration redraws the UI.
+    pub fn clear_state(&mut self) -> &mut Self {
+        self.state.clear();
+        self.fingerprint = String::from("<>");
+        self
+    }
+
+    /// Saves the UI state as a JSON object (needs the `serde` feature).
+    #[cfg(feature = "serde")]
+    pub fn save_state(&self, path: &str) -> Result<(), MarkupError> {
+        let json = serde_json::to_string_pretty(&self.state)
+            .map_err(|e| MarkupError::State(e.to_string()))?;
+        std::fs::write(path, json).map_err(|e| MarkupError::State(format!("{}: {}", path, e)))
+    }
+
+    /// Restores the UI state saved by `save_state`, the saved values replace
+    /// the current ones with the same key. A missing file does nothing
+    /// (e.g. the first run), an invalid one is an error.
+    #[cfg(feature = "serde")]
+    pub fn load_state(&mut self, path: &str) -> Result<(), MarkupError> {
+        if !Path::new(path).exists() {
+            return Ok(());
+        }
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| MarkupError::State(format!("{}: {}", path, e)))?;
+        let saved: HashMap<String, StateValue> = serde_json::from_str(&json)
+            .map_err(|e| MarkupError::State(format!("{}: {}", path, e)))?;
+        self.state.extend(saved);
+        self.fingerprint = String::from("<>");
+        Ok(())
+    }
+
+    /// Loads the rules of a stylesheet file (same syntax as `<styles>`), they
+    /// replace the current rules with the same name, e.g. to switch themes.
+    pub fn load_styles(&mut self, path: &str) -> Result<(), MarkupError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| MarkupError::Load(format!("Can't read stylesheet {}: {}", path, e)))?;
+        for (rulename, styles) in MarkupParser::<B>::parse_style_rules(&text) {
+            self.global_styles.add_rule(rulename, styles);
+        }
+        self.fingerprint = String::from("<>");
+        Ok(())
+    }
+
+    /// Runs the layout pass for a screen of the given size and returns the
+    /// identifier and the area of every element, without drawing anything.
+    pub fn compute_layout(&self, width: u16, height: u16) -> Vec<(Rect, String)> {
+        match self.root.as_ref() {
+            Some(root) => {
+                let root = MarkupParser::<B>::extract_element(root);
+                let screen = Rect::new(0, 0, width, height);
+                self.process_node(screen, &root, None, None, None, 0)
+                    .into_iter()
+                    .map(|(area, node)| (area, node.id))
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Runs the layout pass in the given backend and returns the area, the
+    /// name and the identifier of every element along with the global styles,
+    /// so the structure of a markup can be checked by tests. With `print` they
+    /// are dumped to the stdout too.
+    ///
+    pub fn test_check(
+        &self,
+        backend: B,
+        print: bool,
+    ) -> Result<(Vec<CheckedElement>, &StylesStorage), Box<dyn std::error::Error>> {
+        let mut elements: Vec<CheckedElement> = vec![];
+        let elm = self.root.clone();
+        if elm.is_some() {
+            let mut terminal = Terminal::new(backend)?;
+            let root = MarkupParser::<B>::get_element(elm);
+            terminal.draw(|frame| {
+                let drawables = self.process_node(frame.size(), &root, None, None, None, 0);
+                if print {
+                    let ids: Vec<String> = drawables
+                        .iter()
+                        .map(|x| format!("{}#{}", x.1.name, x.1.id))
+                        .collect();
+                    println!("{:#?}", drawables);
+                    println!("{:#?}", ids);
+                }
+                elements = drawables
+                    .into_iter()
+                    .map(|(area, node)| (area, node.name, node.id))
+                    .collect();
+            })?;
+        }
+        if print {
+            println!("{:#?}", self.global_styles);
+        }
+        Ok((elements, &self.global_styles))
     }
 
-    fn process_node(
-        &self,
-        frame: &mut Frame<B>,
-        node: &MarkupElement,
-        depends_on: Option<MarkupElement>,
-        place: Option<Rect>,
-        margin: Option<u16>,
-        count: usize,
-    ) -> Vec<(Rect, MarkupElement)> {
-        let name = node.name.clone();
-        let name = name.as_str();
-        let values: Vec<(Rect, MarkupElement)> = match name {
-            "styles" => vec![],
-            "layout" => {
-                self.process_layout(frame.borrow_mut(), node, depends_on, place, margin, count)
+    /// Applies the built-in key handling (focus navigation and actions) to a
+    /// key event, updating the state with the resulting response.
+    /// Keys bound in a `keybindings` element run their action first, keys
+    /// nothing else consumed are forwarded to the `on_unhandled_key` callback.
+    /// Key releases (and repeats with `set_key_repeat(false)`) are ignored,
+    /// `ui_loop` doesn't even forward them (see `is_forwarded`).
+    ///
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> EventResponse {
+        // some terminals (e.g. on Windows) send the release of the keys too
+        let repeated = key_event.kind == KeyEventKind::Repeat && !self.key_repeat;
+        if key_event.kind == KeyEventKind::Release || repeated {
+            return EventResponse::NOOP;
+        }
+        let combo = key_combo_from_event(&key_event);
+        if let Some(action) = self.keybindings.get(&combo).cloned() {
+            return self.dispatch(&action);
+        }
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        let response = match key_event.code {
+            // Ctrl+Tab and Ctrl+Shift+Tab switch the current tabs (not in dialogs)
+            KeyCode::Tab | KeyCode::BackTab if ctrl && self.contexts.is_empty() => {
+                let delta = if shift || key_event.code == KeyCode::BackTab {
+                    -1
+                } else {
+                    1
+                };
+                match self.select_tab_by_offset(delta) {
+                    Some(response) => response,
+                    None => self.unhandled_key(key_event),
+                }
             }
-            "container" => {
-                self.process_block(frame.borrow_mut(), node, depends_on, place, margin, count)
+            KeyCode::Tab if shift => {
+                self.go_prev();
+                EventResponse::NOOP
             }
-            "block" => {
-                self.process_block(frame.borrow_mut(), node, depends_on, place, margin, count)
+            KeyCode::Tab => {
+                self.go_next();
+                EventResponse::NOOP
             }
-            _ => {
-                let res =
-                    self.process_other(frame.borrow_mut(), node, depends_on, place, margin, count);
-                if let Some(value) = res {
-                    value
-                } else {
-                    warn!("Unknown node type \"{}\"", name);
-                    vec![]
+            KeyCode::BackTab => {
+                self.go_prev();
+                EventResponse::NOOP
+            }
+            KeyCode::Down if self.focused_is_scrollable() => {
+                self.scroll_focused(1);
+                EventResponse::NOOP
+            }
+            KeyCode::Up if self.focused_is_scrollable() => {
+                self.scroll_focused(-1);
+                EventResponse::NOOP
+            }
+            KeyCode::Down if self.focused_is_radio_group() => {
+                self.select_radio(1);
+                EventResponse::NOOP
+            }
+            KeyCode::Up if self.focused_is_radio_group() => {
+                self.select_radio(-1);
+                EventResponse::NOOP
+            }
+            KeyCode::Down | KeyCode::Right if !self.focused_consumes_arrows() => {
+                self.go_next();
+                EventResponse::NOOP
+            }
+            KeyCode::Up | KeyCode::Left if !self.focused_consumes_arrows() => {
+                self.go_prev();
+                EventResponse::NOOP
+            }
+            // 1-9 select a tab of the tabs on screen
+            KeyCode::Char(digit @ '1'..='9')
+                if self.contexts.is_empty()
+                    && !self.focused_consumes_arrows()
+                    && !key_event
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                let position = digit.to_digit(10).unwrap_or(0) as usize;
+                match self.select_tab_by_position(position) {
+                    Some(response) => response,
+                    None => self.unhandled_key(key_event),
                 }
             }
+            KeyCode::Enter if self.current > -1 => self.do_action(),
+            // Space activates buttons too, other elements (e.g. editable
+            // widgets) get it as a regular key
+            KeyCode::Char(' ')
+                if self
+                    .focused_name()
+                    .map(|name| SPACE_ACTIVATED_NAMES.contains(&name.as_str()))
+                    .unwrap_or(false)
+                    && !key_event
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.do_action()
+            }
+            KeyCode::Esc if !self.contexts.is_empty() => {
+                self.close_top_dialog();
+                EventResponse::NOOP
+            }
+            _ => self.unhandled_key(key_event),
         };
-        values
+        self.apply_response(&response);
+        response
     }
 
-    pub fn add_action(&mut self, name: &str, action: ActionCallback) -> &mut Self {
-        self.actions.add_action(String::from(name), action);
-        self
+    /// Applies the built-in mouse handling: the scroll wheel scrolls the
+    /// scrollable element under the cursor, other mouse events are ignored.
+    ///
+    pub fn handle_mouse(&mut self, mouse_event: MouseEvent) -> EventResponse {
+        let delta = match mouse_event.kind {
+            MouseEventKind::ScrollDown => 1,
+            MouseEventKind::ScrollUp => -1,
+            _ => return EventResponse::NOOP,
+        };
+        if let Some(id) = self.scrollable_at(mouse_event.column, mouse_event.row) {
+            self.scroll_element(&id, delta);
+        }
+        EventResponse::NOOP
     }
 
-    fn can_be_drawn(&self, node: MarkupElement, drawn: &[String]) -> bool {
-        let others = node.dependencies;
-        if others.is_empty() {
-            return true;
-        }
-        let mut res = false;
-        for eid in others {
-            if drawn.contains(&eid) {
-                res = true;
+    /// Hides the dialog on top of the context stack setting its `show` state
+    /// key to "false".
+    fn close_top_dialog(&mut self) {
+        let top = self.contexts.last().map(|ctx| ctx.0.clone());
+        if let Some(dialog) = top.and_then(|id| self.find_element(&id)) {
+            let show_flag = extract_attribute(&dialog.attributes, "show");
+            if !show_flag.is_empty() {
+                self.state.insert(show_flag, false.into());
             }
+            self.remove_context(&dialog);
         }
-        res
     }
 
-    fn get_fingerprint(&self) -> String {
-        let idxd: Vec<String> = self.indexed_elements.iter().map(|x| x.id.clone()).collect();
-        let mut state_fngrprnt = format!(
-            "{}:{}:{}:",
-            self.current,
-            self.contexts.len(),
-            idxd.join("~")
-        );
-        for (key, value) in self.state.clone().iter() {
-            state_fngrprnt = format!("{}-{}_{}", state_fngrprnt, key, value);
-        }
-        state_fngrprnt
+    /// Looks for an element in the parsed tree by its identifier.
+    pub fn find_element(&self, id: &str) -> Option<MarkupElement> {
+        self.find_node(id)
+            .map(|node| MarkupParser::<B>::extract_element(&node))
     }
 
-    fn update_fingerprint(&mut self) {
-        let state_fngrprnt = self.get_fingerprint();
-        self.fingerprint = state_fngrprnt;
+    fn find_node(&self, id: &str) -> Option<Rc<RefCell<MarkupElement>>> {
+        fn find(node: &Rc<RefCell<MarkupElement>>, id: &str) -> Option<Rc<RefCell<MarkupElement>>> {
+            if node.as_ref().borrow().id.eq(id) {
+                return Some(node.clone());
+            }
+            let elm = node.as_ref().borrow();
+            elm.children.iter().find_map(|child| find(child, id))
+        }
+        self.root.as_ref().and_then(|root| find(root, id))
     }
 
-    /// Render the current state of the tree
-    ///
-    pub fn render_ui(&mut self, frame: &mut Frame<B>) -> Result<bool, String> {
-        let elm = self.root.clone();
-        if elm.is_some() {
-            let root = MarkupParser::<B>::get_element(elm);
-            let drawables = self.process_node(frame.borrow_mut(), &root, None, None, None, 0);
-            let mut drawn: Vec<String> = vec![];
-            drawables.iter().for_each(|pair| {
-                let area = pair.0;
-                let node = pair.1.clone();
-                if self.can_be_drawn(node.clone(), &drawn) {
-                    // println!("{} can be drawn...", &node.id);
-                    let done = self.draw_element(frame, area, &node);
-                    if done {
-                        drawn.push(node.id);
-                    }
-                } else {
-                    // println!("{} cant be drawn...", &node.id);
-                }
-            });
-            Ok(true)
+    fn unhandled_key(&mut self, key_event: KeyEvent) -> EventResponse {
+        info!("{:?}", key_event);
+        if let Some(callback) = self.unhandled_key_callback.as_mut() {
+            callback(key_event, &mut self.state)
         } else {
-            let err = "Critical error on render process.".to_string();
-            Err(err)
+            EventResponse::NOOP
         }
     }
 
-    pub fn add_context(&mut self, node: &MarkupElement) {
-        let loc = self.contexts.len();
-        let current = self.contexts.get(loc);
-        let must_insert = current.is_some() && !current.unwrap().0.eq(&node.id);
-        if loc == 0 || must_insert {
-            self.contexts
-                .push((node.id.clone(), self.indexed_elements.clone()));
-            let chld: Vec<MarkupElement> = node
-                .clone()
-                .children
-                .iter()
-                .map(|x| x.as_ref().borrow().clone())
-                .filter(|x| x.order > -1)
-                .collect();
-            self.indexed_elements = chld;
-            self.current = -1;
+    /// Updates the parser with an event response, returns true if the
+    /// response asks to quit.
+    fn apply_response(&mut self, response: &EventResponse) -> bool {
+        match response {
+            EventResponse::QUIT => {
+                return true;
+            }
+            EventResponse::STATE(new_state) => {
+                self.state = new_state.clone();
+            }
+            EventResponse::CLEANFOCUS(new_state) => {
+                self.state = new_state.clone();
+                self.current = -1;
+            }
+            EventResponse::THEN(new_state, next_action) => {
+                self.state = new_state.clone();
+                self.pending_actions.push(next_action.clone());
+            }
+            EventResponse::NOOP => {}
         }
-        self.fingerprint = String::from("<>");
+        false
     }
 
-    pub fn remove_context(&mut self, node: &MarkupElement) {
-        let loc = self.contexts.len();
-        if loc > 0 {
-            let partial = self.contexts[loc - 1].clone();
-            if partial.0.eq(&node.id) {
-                self.indexed_elements = partial.1;
-                self.contexts.pop();
-                self.current = -1;
+    /// Runs the actions queued by `THEN` responses, the event loop calls it
+    /// after every render. Actions queued meanwhile wait for the next call.
+    /// Returns true if an action asks to quit.
+    pub fn run_pending_actions(&mut self) -> bool {
+        self.chained_actions += 1;
+        let mut quit = false;
+        for action in std::mem::take(&mut self.pending_actions) {
+            if let EventResponse::QUIT = self.dispatch(&action) {
+                quit = true;
             }
         }
-        self.fingerprint = String::from("<>");
+        quit
     }
 
-    pub fn test_check(&self, backend: B) -> Result<(), Box<dyn std::error::Error>> {
-        let elm = self.root.clone();
-        if elm.is_some() {
-            let mut terminal = Terminal::new(backend)?;
-            let root = MarkupParser::<B>::get_element(elm);
-            terminal.draw(|frame| {
-                let drawables = self.process_node(frame.borrow_mut(), &root, None, None, None, 0);
-                let ids: Vec<String> = drawables
-                    .iter()
-                    .map(|x| format!("{}#{}", x.1.name, x.1.id))
-                    .collect();
-                println!("{:#?}", drawables);
-                println!("{:#?}", ids);
-            })?;
-        }
-        println!("{:#?}", self.global_styles);
-        Ok(())
+    /// Checks if there are actions queued by `THEN` responses.
+    pub fn has_pending_actions(&self) -> bool {
+        !self.pending_actions.is_empty()
+    }
+
+    /// Checks if the queued actions can run before the next tick: an action
+    /// queuing itself with `THEN` would keep the loop from reading the keys.
+    pub fn pending_actions_ready(&self) -> bool {
+        self.has_pending_actions() && self.chained_actions < MAX_CHAINED_ACTIONS
     }
 
     /// Starts a render loop. the loop receive a callback thar will return true
@@ -1153,7 +3598,7 @@ impl<B: Backend> MarkupParser<B> {
     pub fn ui_loop(
         &mut self,
         backend: B,
-        on_event: impl Fn(crossterm::event::KeyEvent, HashMap<String, String>) -> EventResponse,
+        on_event: impl Fn(crossterm::event::KeyEvent, HashMap<String, StateValue>) -> EventResponse,
         // on_event: impl Fn(crossterm::event::KeyEvent) -> bool,
     ) -> Result<(), Box<dyn std::error::Error>>
 // pub fn ui_loop<Fut>(
@@ -1162,17 +3607,39 @@ impl<B: Backend> MarkupParser<B> {
      // where
      //     Fut: Future<Output = bool>,
     {
-        if self.error.is_some() {
-            panic!("{}", self.error.clone().unwrap());
+        self.ui_loop_ctx(backend, |key_event, parser| {
+            on_event(key_event, parser.state.clone())
+        })
+    }
+
+    /// Same as `ui_loop` but the callback receives the parser, so it can check
+    /// the focused element (`focused_id`), run actions (`dispatch`) or update
+    /// the state (`set_state`).
+    ///
+    /// - *on_event*: callback that receive a key event and the parser.
+    ///
+    pub fn ui_loop_ctx(
+        &mut self,
+        backend: B,
+        on_event: impl Fn(crossterm::event::KeyEvent, &mut MarkupParser<B>) -> EventResponse,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(error) = self.error.clone() {
+            let error = self
+                .markup_error
+                .clone()
+                .unwrap_or(MarkupError::Load(error));
+            return Err(Box::new(error));
         }
 
         let mut terminal = Terminal::new(backend)?;
 
-        enable_raw_mode().expect("Can't run in raw mode.");
+        // the guard restores the terminal on every exit path, panics included
+        let guard = TerminalGuard::new(self.alternate_screen)?;
         terminal.clear()?;
 
-        let (tx, rx) = mpsc::channel::<Event<KeyEvent>>();
-        let tick_rate = Duration::from_millis(200);
+        let (tx, rx) = mpsc::channel::<Event<CEvent>>();
+        let tick_rate = self.tick_rate;
+        let key_repeat = self.key_repeat;
 
         thread::spawn(move || {
             let mut last_tick = Instant::now();
@@ -1182,8 +3649,9 @@ impl<B: Backend> MarkupParser<B> {
                     .unwrap_or_else(|| Duration::from_secs(0));
 
                 if event::poll(timeout).expect("poll works") {
-                    if let CEvent::Key(key) = event::read().expect("can read events") {
-                        tx.send(Event::Input(key)).expect("can send events");
+                    let evt = event::read().expect("can read events");
+                    if MarkupParser::<B>::is_forwarded(&evt, key_repeat) {
+                        tx.send(Event::Input(evt)).expect("can send events");
                     }
                 }
 
@@ -1192,93 +3660,166 @@ impl<B: Backend> MarkupParser<B> {
                 }
             }
         });
-        let mut error_info: Option<String> = None;
-        let mut should_quit: bool = false;
+        let result = self.run_loop(&mut terminal, &rx, &on_event);
+
+        // the terminal is restored even when the loop failed
+        drop(guard);
+        terminal.show_cursor()?;
+        // leaving the alternate screen already brought back the previous contents
+        if !self.alternate_screen {
+            terminal.clear()?;
+        }
+        result
+    }
+
+    fn run_loop(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        rx: &mpsc::Receiver<Event<CEvent>>,
+        on_event: &impl Fn(crossterm::event::KeyEvent, &mut MarkupParser<B>) -> EventResponse,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         loop {
-            let new_fprnt = self.get_fingerprint();
-            if !new_fprnt.eq(&self.fingerprint) {
+            if self.needs_redraw() {
+                let mut error_info: Option<String> = None;
                 terminal.draw(|frame| {
-                    let res = self.render_ui(frame);
-                    if res.is_ok() {
-                        self.update_fingerprint();
+                    if let Err(error) = self.render_ui(frame) {
+                        error_info = Some(error);
                     } else {
-                        error_info = res.err();
-                        should_quit = true;
+                        self.update_fingerprint();
                     }
                 })?;
+                if let Some(error) = error_info {
+                    return Err(Box::new(MarkupError::Render(error)));
+                }
             }
-            let evt: Event<crossterm::event::KeyEvent> = rx.recv()?;
-            if let Event::Input(key_event) = evt {
-                let event = key_event;
-                match event.code {
-                    KeyCode::Tab => {
-                        self.go_next();
-                    }
-                    KeyCode::BackTab => {
-                        self.go_prev();
-                    }
-                    KeyCode::Enter => {
-                        let res = self.do_action();
-                        match res {
-                            EventResponse::QUIT => {
-                                should_quit = true;
-                            }
-                            EventResponse::STATE(state) => {
-                                self.state = state;
-                            }
-                            EventResponse::CLEANFOCUS(state) => {
-                                self.state = state;
-                                self.current = -1;
-                            }
-                            _ => {}
-                        }
-                    }
-                    _ => {
-                        info!("{:?}", key_event);
-                    }
+            // deferred actions run once their previous state is drawn
+            if self.pending_actions_ready() {
+                if self.run_pending_actions() {
+                    return Ok(());
                 }
-                let response =
-                    on_event(key_event as crossterm::event::KeyEvent, self.state.clone());
-                match response {
-                    EventResponse::QUIT => {
+                continue;
+            }
+            let evt: Event<CEvent> = rx.recv()?;
+            if let Event::Tick = evt {
+                self.tick();
+            }
+            // mouse events are handled here, they don't reach on_event
+            if let Event::Input(CEvent::Mouse(mouse_event)) = evt {
+                self.handle_mouse(mouse_event);
+            }
+            if let Event::Input(CEvent::Key(key_event)) = evt {
+                let mut should_quit = false;
+                // Esc is consumed when it closes an open dialog
+                let closes_dialog = key_event.code == KeyCode::Esc && !self.contexts.is_empty();
+                if let EventResponse::QUIT = self.handle_key(key_event) {
+                    should_quit = true;
+                }
+                if !closes_dialog {
+                    let response = on_event(key_event as crossterm::event::KeyEvent, self);
+                    if self.apply_response(&response) {
                         should_quit = true;
                     }
-                    EventResponse::STATE(new_state) => {
-                        self.state = new_state;
-                    }
-                    EventResponse::CLEANFOCUS(new_state) => {
-                        self.state = new_state;
-                        self.current = -1;
-                    }
-                    EventResponse::NOOP => {}
                 }
                 if should_quit {
-                    break;
+                    return Ok(());
                 }
             }
         }
-
-        disable_raw_mode()?;
-        terminal.show_cursor()?;
-        terminal.clear()?;
-        if error_info.is_some() {
-            panic!("{}", error_info.unwrap());
-        }
-        Ok(())
     }
 
     // Static
 
-    fn get_constraints(node: MarkupElement) -> Vec<Constraint> {
-        let mut constraints: Vec<Constraint> = vec![];
-        if !node.children.is_empty() {
-            for (_position, base_child) in node.children.iter().enumerate() {
-                let child = base_child.as_ref().borrow().clone();
-                let constraint = extract_attribute(child.attributes.clone(), "constraint");
-                constraints.push(MarkupParser::<B>::get_constraint(constraint));
-            }
+    fn get_constraints(children: &[Rc<RefCell<MarkupElement>>], available: u16) -> Vec<Constraint> {
+        let constraints: Vec<String> = children
+            .iter()
+            .map(|base_child| {
+                extract_attribute(&base_child.as_ref().borrow().attributes, "constraint")
+            })
+            .collect();
+        MarkupParser::<B>::get_fraction_constraints(&constraints, available)
+    }
+
+    /// Logs a problem found in the layout pass and keeps it for `warnings()`,
+    /// once even if the layout is computed again.
+    fn add_layout_warning(&self, warning: String) {
+        let mut layout_warnings = self.layout_warnings.borrow_mut();
+        if !layout_warnings.contains(&warning) {
+            warn!("{}", warning);
+            layout_warnings.push(warning);
+        }
+    }
+
+    /// Percentages over 100% make `tui` clip the last children, they are
+    /// reported as warnings and, with `normalize="true"` in the layout, scaled
+    /// to sum 100%.
+    fn check_percentages(
+        &self,
+        node: &MarkupElement,
+        constraints: Vec<Constraint>,
+    ) -> Vec<Constraint> {
+        let total: u32 = constraints
+            .iter()
+            .map(|constraint| match constraint {
+                Constraint::Percentage(value) => u32::from(*value),
+                _ => 0,
+            })
+            .sum();
+        if total <= 100 {
+            return constraints;
+        }
+        self.add_layout_warning(format!("Percentages of layout #{} sum {}%", node.id, total));
+        if !extract_attribute(&node.attributes, "normalize").eq("true") {
+            return constraints;
         }
         constraints
+            .into_iter()
+            .map(|constraint| match constraint {
+                Constraint::Percentage(value) => {
+                    Constraint::Percentage((u32::from(value) * 100 / total) as u16)
+                }
+                other => other,
+            })
+            .collect()
+    }
+
+    /// Children with `min-height`/`max-height` (`min-width`/`max-width` in
+    /// horizontal layouts) get a `Length` with the size of their first split
+    /// clamped to those bounds. `None` when no child has bounds.
+    fn get_bounded_constraints(
+        children: &[Rc<RefCell<MarkupElement>>],
+        direction: &Direction,
+        constraints: &[Constraint],
+        chunks: &[Rect],
+    ) -> Option<Vec<Constraint>> {
+        let (min_key, max_key) = match direction {
+            Direction::Vertical => ("min-height", "max-height"),
+            Direction::Horizontal => ("min-width", "max-width"),
+        };
+        let mut bounded = false;
+        let constraints = children
+            .iter()
+            .zip(constraints.iter().zip(chunks.iter()))
+            .map(|(base_child, (constraint, chunk))| {
+                let child = base_child.as_ref().borrow();
+                let min = extract_attribute(&child.attributes, min_key).parse::<u16>();
+                let max = extract_attribute(&child.attributes, max_key).parse::<u16>();
+                if min.is_err() && max.is_err() {
+                    return *constraint;
+                }
+                bounded = true;
+                let size = match direction {
+                    Direction::Vertical => chunk.height,
+                    Direction::Horizontal => chunk.width,
+                };
+                let size = size.max(min.unwrap_or(0)).min(max.unwrap_or(u16::MAX));
+                Constraint::Length(size)
+            })
+            .collect();
+        if bounded {
+            Some(constraints)
+        } else {
+            None
+        }
     }
 
     pub fn get_element(node: Option<Rc<RefCell<MarkupElement>>>) -> MarkupElement {
@@ -1292,10 +3833,106 @@ impl<B: Backend> MarkupParser<B> {
         r
     }
 
+    /// Checks if `ui_loop` handles a terminal event: keys and mouse events
+    /// except the key releases (Windows terminals send both the press and the
+    /// release, actions would run twice) and, without `key_repeat`, the key
+    /// repeats.
+    pub fn is_forwarded(evt: &CEvent, key_repeat: bool) -> bool {
+        match evt {
+            CEvent::Key(key) => match key.kind {
+                KeyEventKind::Press => true,
+                KeyEventKind::Repeat => key_repeat,
+                KeyEventKind::Release => false,
+            },
+            CEvent::Mouse(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Elements with `preserve-whitespace="true"` keep the indentation and
+    /// the line breaks of their text.
+    pub fn preserves_whitespace(node: &MarkupElement) -> bool {
+        extract_attribute(&node.attributes, "preserve-whitespace").eq("true")
+    }
+
+    /// Checks if a text has inline elements (`b`, `i`, `u` or `c`), its text
+    /// is split in `#text` children then.
+    fn has_inline_children(node: &MarkupElement) -> bool {
+        node.children
+            .iter()
+            .any(|child| INLINE_NAMES.contains(&child.as_ref().borrow().name.as_str()))
+    }
+
+    fn text_node(
+        text: &str,
+        parent: &Rc<RefCell<MarkupElement>>,
+        deep: usize,
+    ) -> Rc<RefCell<MarkupElement>> {
+        Rc::new(RefCell::new(MarkupElement {
+            deep,
+            id: String::new(),
+            name: String::from("#text"),
+            order: -1,
+            text: Some(text.to_string()),
+            attributes: HashMap::new(),
+            children: vec![],
+            parent_node: Some(parent.clone()),
+            dependencies: vec![],
+        }))
+    }
+
+    /// The pieces of text of an element with inline elements and their styles,
+    /// patched over `styles`.
+    fn inline_spans(node: &MarkupElement, styles: Style) -> Vec<(String, Style)> {
+        let mut spans = vec![];
+        for child in node.children.iter() {
+            let child = child.as_ref().borrow();
+            let mut styles = styles;
+            match child.name.as_str() {
+                "#text" => {}
+                "b" => styles = styles.add_modifier(Modifier::BOLD),
+                "i" => styles = styles.add_modifier(Modifier::ITALIC),
+                "u" => styles = styles.add_modifier(Modifier::UNDERLINED),
+                "c" => {
+                    if let Some(color) = child.attributes.get("fg") {
+                        styles = styles.fg(color_from_str(color));
+                    }
+                    if let Some(color) = child.attributes.get("bg") {
+                        styles = styles.bg(color_from_str(color));
+                    }
+                }
+                _ => continue,
+            }
+            if MarkupParser::<B>::has_inline_children(&child) {
+                spans.extend(MarkupParser::<B>::inline_spans(&child, styles));
+            } else {
+                spans.push((child.text.clone().unwrap_or_default(), styles));
+            }
+        }
+        spans
+    }
+
     pub fn is_widget(node_name: &str) -> bool {
         WIDGET_NAMES.contains(&node_name)
     }
 
+    pub fn is_vertical_tabs(node: &MarkupElement) -> bool {
+        extract_attribute(&node.attributes, "orientation").eq("vertical")
+    }
+
+    pub fn is_disabled(node: &MarkupElement) -> bool {
+        extract_attribute(&node.attributes, "disabled").eq("true")
+    }
+
+    /// Indexed elements can get the focus unless they are disabled.
+    pub fn is_focusable(node: &MarkupElement) -> bool {
+        node.order != -1 && !MarkupParser::<B>::is_disabled(node)
+    }
+
+    pub fn is_scrollable(node: &MarkupElement) -> bool {
+        node.name.eq("p") && extract_attribute(&node.attributes, "scrollable").eq("true")
+    }
+
     pub fn is_layout(node_name: &str) -> bool {
         node_name.eq("layout")
     }
@@ -1328,8 +3965,49 @@ impl<B: Backend> MarkupParser<B> {
         border
     }
 
+    /// Borders of an element, `border-focus` replaces `border` while the
+    /// element is focused.
+    fn get_focused_border(node: &MarkupElement, focus: bool) -> Borders {
+        let border_focus = extract_attribute(&node.attributes, "border-focus");
+        if focus && !border_focus.is_empty() {
+            MarkupParser::<B>::get_border(border_focus.as_str())
+        } else {
+            MarkupParser::<B>::get_border(extract_attribute(&node.attributes, "border").as_str())
+        }
+    }
+
+    /// Like `get_node_border_type` but `border-focus-type` replaces
+    /// `border-type` while the element is focused.
+    fn get_focused_border_type(
+        node: &MarkupElement,
+        focus: bool,
+        default: BorderType,
+    ) -> BorderType {
+        let border_focus_type = extract_attribute(&node.attributes, "border-focus-type");
+        if focus && !border_focus_type.is_empty() {
+            get_border_type(border_focus_type.as_str())
+        } else {
+            MarkupParser::<B>::get_node_border_type(node, default)
+        }
+    }
+
+    /// Border type from the `border-type` attribute, `default` when it is not set.
+    pub fn get_node_border_type(node: &MarkupElement, default: BorderType) -> BorderType {
+        let border_type = extract_attribute(&node.attributes, "border-type");
+        if border_type.is_empty() {
+            default
+        } else {
+            get_border_type(border_type.as_str())
+        }
+    }
+
+    /// Maps a `constraint` attribute to a `Constraint`: `20%` (percentage),
+    /// `10min`, `10max`, `1:3` (ratio), `*` (the rest of the space, as
+    /// `Constraint::Min(0)`) or a length.
     pub fn get_constraint(constraint: String) -> Constraint {
-        let res = if constraint.ends_with('%') {
+        let res = if constraint.trim().eq("*") {
+            Constraint::Min(0)
+        } else if constraint.ends_with('%') {
             let constraint_value = constraint.replace('%', "");
             let constraint_value = constraint_value.parse::<u16>().unwrap_or(1);
             Constraint::Percentage(constraint_value)
@@ -1354,17 +4032,142 @@ impl<B: Backend> MarkupParser<B> {
         res
     }
 
-    pub fn get_direction(node: &MarkupElement) -> Direction {
-        let direction = extract_attribute(node.attributes.clone(), "direction");
-        if direction.eq("vertical") {
-            Direction::Vertical
+    /// Like `get_constraint` but also accepts constraints relative to the
+    /// space available in the parent (`available` cells along the layout
+    /// direction):
+    ///
+    /// - `20min%`: at least 20% of the available space, growing when possible.
+    ///   Computed as `Constraint::Min(available * 20 / 100)`.
+    /// - `20max%`: at most 20% of the available space.
+    ///   Computed as `Constraint::Max(available * 20 / 100)`.
+    ///
+    pub fn get_sized_constraint(constraint: String, available: u16) -> Constraint {
+        let relative_value = |suffix: &str| {
+            let constraint_value = constraint.replace(suffix, "");
+            let constraint_value = constraint_value.parse::<u32>().unwrap_or(1);
+            (u32::from(available) * constraint_value.min(100) / 100) as u16
+        };
+        if constraint.ends_with("min%") {
+            Constraint::Min(relative_value("min%"))
+        } else if constraint.ends_with("max%") {
+            Constraint::Max(relative_value("max%"))
         } else {
-            Direction::Horizontal
+            MarkupParser::<B>::get_constraint(constraint)
+        }
+    }
+
+    /// Like `get_sized_constraint` for all the children of a layout, adding the
+    /// fractional units: the space left by the other constraints is shared by
+    /// the `fr` ones in proportion to their weights (`2fr` takes twice the
+    /// space of `1fr`). `tui` has no such constraint, so they become lengths.
+    ///
+    pub fn get_fraction_constraints(constraints: &[String], available: u16) -> Vec<Constraint> {
+        let weight = |constraint: &str| {
+            let weight = constraint.trim().trim_end_matches("fr").trim();
+            if weight.is_empty() {
+                1.0
+            } else {
+                weight.parse::<f64>().unwrap_or(1.0).max(0.0)
+            }
+        };
+        let is_fraction = |constraint: &String| constraint.trim().ends_with("fr");
+        let mut res: Vec<Constraint> = constraints
+            .iter()
+            .map(|constraint| {
+                MarkupParser::<B>::get_sized_constraint(constraint.to_string(), available)
+            })
+            .collect();
+        let total: f64 = constraints
+            .iter()
+            .filter(|constraint| is_fraction(constraint))
+            .map(|constraint| weight(constraint))
+            .sum();
+        if total <= 0.0 {
+            return res;
+        }
+        let used: u32 = constraints
+            .iter()
+            .zip(res.iter())
+            .filter(|(constraint, _)| !is_fraction(constraint))
+            .map(|(_, constraint)| match constraint {
+                Constraint::Percentage(value) => u32::from(available) * u32::from(*value) / 100,
+                Constraint::Ratio(x, y) => u32::from(available) * x / (*y).max(1),
+                Constraint::Length(value) | Constraint::Min(value) | Constraint::Max(value) => {
+                    u32::from(*value)
+                }
+            })
+            .sum();
+        let left = u32::from(available).saturating_sub(used) as f64;
+        // the cells are rounded on the accumulated weights, so they fill the space
+        let mut accumulated = 0.0;
+        for (position, constraint) in constraints.iter().enumerate() {
+            if is_fraction(constraint) {
+                let start = (left * accumulated / total).round();
+                accumulated += weight(constraint);
+                let end = (left * accumulated / total).round();
+                res[position] = Constraint::Length((end - start) as u16);
+            }
+        }
+        res
+    }
+
+    /// Constraints centering a dialog along one direction. The size is a
+    /// percentage of the `available` cells (`width="50%"`) or a number of
+    /// cells (`height="10"`) and the remaining space is split in two equal
+    /// margins. Without size the `default` percentages are used.
+    ///
+    pub fn get_dialog_constraints(
+        size: String,
+        available: u16,
+        default: [u16; 3],
+    ) -> Vec<Constraint> {
+        let size = size.trim();
+        if size.is_empty() {
+            return default
+                .iter()
+                .map(|value| Constraint::Percentage(*value))
+                .collect();
+        }
+        let cells = match size.strip_suffix('%') {
+            Some(percentage) => {
+                let percentage = percentage.trim().parse::<u32>().unwrap_or(50).min(100);
+                (u32::from(available) * percentage / 100) as u16
+            }
+            None => size.parse::<u16>().unwrap_or(available).min(available),
+        };
+        let side = (available - cells) / 2;
+        vec![
+            Constraint::Length(side),
+            Constraint::Length(cells),
+            Constraint::Min(0),
+        ]
+    }
+
+    /// Parses a size in cells (e.g. `"5"`) or as a percentage of `available`
+    /// (e.g. `"50%"`), limited to `available`. `None` when it isn't valid.
+    pub fn parse_size(size: &str, available: u16) -> Option<u16> {
+        let size = size.trim();
+        let cells = match size.strip_suffix('%') {
+            Some(percentage) => {
+                let percentage = percentage.trim().parse::<u32>().ok()?.min(100);
+                (u32::from(available) * percentage / 100) as u16
+            }
+            None => size.parse::<u16>().ok()?,
+        };
+        Some(cells.min(available))
+    }
+
+    /// `vertical` (or `v`) and `horizontal` (or `h`), horizontal by default.
+    pub fn get_direction(node: &MarkupElement) -> Direction {
+        let direction = extract_attribute(&node.attributes, "direction");
+        match direction.trim().to_lowercase().as_str() {
+            "vertical" | "v" => Direction::Vertical,
+            _ => Direction::Horizontal,
         }
     }
 
     pub fn get_alignment(node: &MarkupElement) -> Alignment {
-        let align_text = extract_attribute(node.attributes.clone(), "align");
+        let align_text = extract_attribute(&node.attributes, "align");
         match align_text.as_str() {
             "center" => Alignment::Center,
             "left" => Alignment::Left,
@@ -1375,32 +4178,35 @@ impl<B: Backend> MarkupParser<B> {
 
     pub fn process_styles(node: MarkupElement) -> StylesStorage {
         let mut global_styles = StylesStorage::new();
-        if node.text.is_some() {
-            let text = node.text.unwrap();
-            let text = text
-                .replace(['\n', '\r', ' '], "")
-                .replace('{', " {")
-                .replace('}', "}\n");
-            let rules: Vec<_> = text
-                .split('\n')
-                .filter(|x| !x.is_empty())
-                .map(|text| {
-                    let nt = String::from(text);
-                    let rule_info = nt.replace('}', "");
-                    let rule_info: Vec<String> = rule_info.split(" {").map(String::from).collect();
-                    let rules = rule_info;
-                    let rulename: String = rules.get(0).unwrap().to_string();
-                    let properties: String = rules.get(1).unwrap().to_string();
-                    (rulename, MarkupParser::<B>::generate_styles(properties))
-                })
-                .collect();
-            for (rulename, styles) in rules.iter() {
-                global_styles.add_rule(rulename.clone(), *styles);
+        if let Some(text) = node.text {
+            for (rulename, styles) in MarkupParser::<B>::parse_style_rules(&text) {
+                global_styles.add_rule(rulename, styles);
             }
         }
         global_styles
     }
 
+    /// Parses the rules of a `<styles>` element or a stylesheet file, e.g.
+    /// `button:focus { fg: white; }`, in the order they are written.
+    pub fn parse_style_rules(text: &str) -> Vec<(String, Style)> {
+        let text = text
+            .replace(['\n', '\r', ' '], "")
+            .replace('{', " {")
+            .replace('}', "}\n");
+        text.split('\n')
+            .filter(|x| !x.is_empty())
+            .map(|text| {
+                let nt = String::from(text);
+                let rule_info = nt.replace('}', "");
+                let rule_info: Vec<String> = rule_info.split(" {").map(String::from).collect();
+                let rules = rule_info;
+                let rulename: String = rules.first().unwrap().to_string();
+                let properties: String = rules.get(1).cloned().unwrap_or_default();
+                (rulename, MarkupParser::<B>::generate_styles(properties))
+            })
+            .collect()
+    }
+
     fn generate_styles(styles_text: String) -> Style {
         let mut res = Style::default();
         if styles_text.len() < 3 {
@@ -1423,22 +4229,76 @@ impl<B: Backend> MarkupParser<B> {
             let color = color_from_str(color);
             res = res.fg(color);
         }
-        if styles.contains_key("weight") {
-            let weight = modifier_from_str(styles.get("weight").unwrap());
-            res = res.add_modifier(weight);
-        }
-        if styles.contains_key("font-decoration") {
-            let decorations = modifiers_from_str(styles.get("font-decoration").unwrap());
-            res = res.patch(decorations);
+        // `weight` and `font-decoration` are aliases, both take modifiers like
+        // `bold|italic`; "no-<modifier>" values remove the modifier (e.g. an
+        // inherited bold), they are applied last so they win over the added ones
+        for key in ["weight", "font-decoration"] {
+            if let Some(value) = styles.get(key) {
+                let (removals, modifiers): (Vec<&str>, Vec<&str>) =
+                    value.split('|').partition(|value| value.starts_with("no-"));
+                if !modifiers.is_empty() {
+                    res = res.patch(modifiers_from_str(&modifiers.join("|")));
+                }
+                for removal in removals {
+                    res = res.remove_modifier(modifier_from_str(&removal[3..]));
+                }
+            }
         }
         // println!("-----------------\n{} \n\n {:#?}\n\n -----------------", &styles_text, res);
         res
     }
 
+    /// Attributes with the styles of an element, in the order they are
+    /// applied: `styles`, then `active_styles` and `focus_styles` on top.
+    fn get_styles_keys(focus: bool, active: bool) -> Vec<&'static str> {
+        let mut keys = vec!["styles"];
+        if active {
+            keys.push("active_styles");
+        }
+        if focus {
+            keys.push("focus_styles");
+        }
+        keys
+    }
+
     pub fn get_styles(node: &MarkupElement, focus: bool, active: bool) -> Style {
-        let key = if focus { "focus_styles" } else { "styles" };
-        let key = if active { "active_styles" } else { key };
-        let styles_text = extract_attribute(node.attributes.clone(), key);
-        MarkupParser::<B>::generate_styles(styles_text)
+        MarkupParser::<B>::get_styles_keys(focus, active)
+            .into_iter()
+            .fold(Style::default(), |styles, key| {
+                let styles_text = extract_attribute(&node.attributes, key);
+                styles.patch(MarkupParser::<B>::generate_styles(styles_text))
+            })
+    }
+}
+
+impl MarkupParser<TestBackend> {
+    /// Renders the current state of the tree in a `TestBackend` of the given
+    /// size and returns the drawn text (no styles), trimming the trailing
+    /// spaces of every line. Useful for logs and snapshot tests.
+    ///
+    pub fn render_plain(&mut self, width: u16, height: u16) -> String {
+        self.render_to_string(width, height, false)
+    }
+
+    /// Renders the current state of the tree in a `TestBackend` of the given
+    /// size and dumps the drawn cells, with `ansi` the styles become ANSI
+    /// escape codes (e.g. for documentation screenshots), otherwise it is the
+    /// same as `render_plain`.
+    ///
+    pub fn render_to_string(&mut self, width: u16, height: u16, ansi: bool) -> String {
+        let backend = TestBackend::new(width, height);
+        let mut terminal = Terminal::new(backend).expect("TestBackend can't fail");
+        terminal
+            .draw(|frame| {
+                if let Err(error) = self.render_ui(frame) {
+                    warn!("{}", error);
+                }
+            })
+            .expect("TestBackend can't fail");
+        if ansi {
+            buffer_to_ansi(terminal.backend().buffer())
+        } else {
+            buffer_to_plain(terminal.backend().buffer())
+        }
     }
 }
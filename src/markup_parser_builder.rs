@@ -0,0 +1,115 @@
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use crate::{
+    backend::backend::Backend, markup_error::MarkupError, markup_parser::MarkupParser,
+    state_value::StateValue, storage::RendererStorage,
+};
+
+/// Configures a `MarkupParser` with named options:
+///
+/// ```
+/// # use tui_markup_renderer::backend::backend::TestBackend;
+/// # use tui_markup_renderer::markup_parser::MarkupParser;
+/// # use std::time::Duration;
+/// let parser = MarkupParser::<TestBackend>::builder()
+///     .path("./tests/assets/sample_single_block.tml")
+///     .strict(true)
+///     .tick_rate(Duration::from_millis(100))
+///     .build();
+/// assert!(parser.is_ok());
+/// ```
+pub struct MarkupParserBuilder<B: Backend> {
+    path: Option<String>,
+    storage: Option<RendererStorage<B>>,
+    state: Option<HashMap<String, StateValue>>,
+    strict: bool,
+    tick_rate: Option<Duration>,
+    key_repeat: bool,
+    alternate_screen: bool,
+}
+
+impl<B: Backend> Default for MarkupParserBuilder<B> {
+    fn default() -> Self {
+        MarkupParserBuilder::new()
+    }
+}
+
+impl<B: Backend> MarkupParserBuilder<B> {
+    pub fn new() -> Self {
+        MarkupParserBuilder {
+            path: None,
+            storage: None,
+            state: None,
+            strict: false,
+            tick_rate: None,
+            key_repeat: true,
+            alternate_screen: true,
+        }
+    }
+
+    /// Markup file to load, required.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Renderers of the custom elements.
+    pub fn storage(mut self, storage: RendererStorage<B>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Initial UI state.
+    pub fn state(mut self, state: HashMap<String, StateValue>) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Unknown element and attribute names make the build fail.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Time between the ticks of `ui_loop`.
+    pub fn tick_rate(mut self, tick_rate: Duration) -> Self {
+        self.tick_rate = Some(tick_rate);
+        self
+    }
+
+    /// Handles the repeated key events sent while a key is held.
+    pub fn key_repeat(mut self, key_repeat: bool) -> Self {
+        self.key_repeat = key_repeat;
+        self
+    }
+
+    /// Runs `ui_loop` in the alternate screen.
+    pub fn alternate_screen(mut self, alternate_screen: bool) -> Self {
+        self.alternate_screen = alternate_screen;
+        self
+    }
+
+    /// Loads the markup, the errors found loading it (including the unknown
+    /// names in strict mode) are returned instead of a failed parser.
+    pub fn build(self) -> Result<MarkupParser<B>, MarkupError> {
+        let path = match self.path {
+            Some(path) => path,
+            None => return Err(MarkupError::Load("Missing markup path".to_string())),
+        };
+        if !Path::new(&path).exists() {
+            let message = format!("Markup file does not exist at {}", path);
+            return Err(MarkupError::Load(message));
+        }
+        let mut parser = MarkupParser::new(path, self.storage, self.state);
+        parser.set_strict(self.strict);
+        if let Some(tick_rate) = self.tick_rate {
+            parser.set_tick_rate(tick_rate);
+        }
+        parser.set_key_repeat(self.key_repeat);
+        parser.set_alternate_screen(self.alternate_screen);
+        match parser.markup_error.clone() {
+            Some(error) => Err(error),
+            None => Ok(parser),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+/// A value of the UI state. Values are shown (and compared with strings) with
+/// their text representation, so string based code keeps working: e.g.
+/// `StateValue::Bool(true) == "true"` and `{{value}}` interpolations.
+/// With the `serde` feature they are plain JSON values (`"text"`, `1`, `true`
+/// or `["a", "b"]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum StateValue {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl StateValue {
+    /// Integer value, strings are parsed.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            StateValue::Int(value) => Some(*value),
+            StateValue::Str(text) => text.trim().parse::<i64>().ok(),
+            _ => None,
+        }
+    }
+
+    /// Float value, strings are parsed.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            StateValue::Int(value) => Some(*value as f64),
+            StateValue::Str(text) => text.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+    }
+
+    /// Boolean value, the strings `"true"` and `"false"` are parsed.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            StateValue::Bool(value) => Some(*value),
+            StateValue::Str(text) => text.trim().parse::<bool>().ok(),
+            _ => None,
+        }
+    }
+
+    /// Items of the value, strings are split by `separator` (an empty string
+    /// has no items).
+    pub fn as_list(&self, separator: &str) -> Vec<String> {
+        match self {
+            StateValue::List(items) => items.clone(),
+            StateValue::Str(text) if text.is_empty() => vec![],
+            StateValue::Str(text) => text.split(separator).map(String::from).collect(),
+            other => vec![other.to_string()],
+        }
+    }
+}
+
+impl Default for StateValue {
+    fn default() -> Self {
+        StateValue::Str(String::new())
+    }
+}
+
+impl fmt::Display for StateValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateValue::Str(text) => write!(f, "{}", text),
+            StateValue::Int(value) => write!(f, "{}", value),
+            StateValue::Bool(value) => write!(f, "{}", value),
+            StateValue::List(items) => write!(f, "{}", items.join(",")),
+        }
+    }
+}
+
+impl From<String> for StateValue {
+    fn from(text: String) -> Self {
+        StateValue::Str(text)
+    }
+}
+
+impl From<&str> for StateValue {
+    fn from(text: &str) -> Self {
+        StateValue::Str(text.to_string())
+    }
+}
+
+impl From<&String> for StateValue {
+    fn from(text: &String) -> Self {
+        StateValue::Str(text.clone())
+    }
+}
+
+impl From<i64> for StateValue {
+    fn from(value: i64) -> Self {
+        StateValue::Int(value)
+    }
+}
+
+impl From<bool> for StateValue {
+    fn from(value: bool) -> Self {
+        StateValue::Bool(value)
+    }
+}
+
+impl From<Vec<String>> for StateValue {
+    fn from(items: Vec<String>) -> Self {
+        StateValue::List(items)
+    }
+}
+
+impl PartialEq<str> for StateValue {
+    fn eq(&self, other: &str) -> bool {
+        self.to_string().eq(other)
+    }
+}
+
+impl PartialEq<&str> for StateValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.to_string().eq(*other)
+    }
+}
+
+impl PartialEq<String> for StateValue {
+    fn eq(&self, other: &String) -> bool {
+        self.to_string().eq(other)
+    }
+}
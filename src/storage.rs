@@ -6,18 +6,30 @@ use std::{
     fmt,
     ops::Deref,
 };
-use tui::layout::Rect;
-use tui::{
+
+use crate::backend::layout::Rect;
+use crate::backend::{
     backend::{Backend, CrosstermBackend, TestBackend},
     Frame,
 };
+use crate::markup_element::MarkupElement;
+use crate::state_value::StateValue;
 
-type Callback<B> = fn(&mut Frame<B>);
+/// Draws a custom element in its computed area. Returns `None` when the
+/// element was not drawn.
+pub type Callback<B> =
+    fn(&MarkupElement, Rect, &HashMap<String, StateValue>, &mut Frame<B>) -> Option<()>;
 
 pub trait IRendererStorage<B: Backend> {
     fn has_component(&self, tagname: &str) -> bool;
     fn add_renderer<'b>(&'b mut self, tagname: &'b str, render: Callback<B>) -> &'b mut Self;
-    fn render(&self, tagname: &str, frame: &mut Frame<B>);
+    fn render(
+        &self,
+        node: &MarkupElement,
+        area: Rect,
+        state: &HashMap<String, StateValue>,
+        frame: &mut Frame<B>,
+    ) -> Option<()>;
 }
 
 #[derive(Default)]
@@ -35,7 +47,7 @@ impl<B: Backend> RendererStorage<B> {
 
 impl<B: Backend> IRendererStorage<B> for RendererStorage<B> {
     fn add_renderer<'b>(&'b mut self, tagname: &'b str, render: Callback<B>) -> &'b mut Self {
-        self.storage.entry(tagname.to_owned()).or_insert(render);
+        self.storage.insert(tagname.to_owned(), render);
         self
     }
 
@@ -43,11 +55,15 @@ impl<B: Backend> IRendererStorage<B> for RendererStorage<B> {
         self.storage.contains_key(tagname)
     }
 
-    fn render(&self, tagname: &str, frame: &mut Frame<B>) {
-        let opt = self.storage.get(tagname);
-        if let Some(f) = opt {
-            f(frame);
-        }
+    fn render(
+        &self,
+        node: &MarkupElement,
+        area: Rect,
+        state: &HashMap<String, StateValue>,
+        frame: &mut Frame<B>,
+    ) -> Option<()> {
+        let f = self.storage.get(&node.name)?;
+        f(node, area, state, frame)
     }
 }
 
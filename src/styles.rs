@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::fmt;
 use std::io::Stdout;
 
-use tui::style::Style;
+use crate::backend::style::Style;
 
 pub trait IStylesStorage {
     fn has_rule(&self, name: String) -> bool;
@@ -26,7 +26,7 @@ impl StylesStorage {
 
 impl IStylesStorage for StylesStorage {
     fn add_rule(&mut self, name: String, styles: Style) -> &mut Self {
-        self.storage.entry(name).or_insert(styles);
+        self.storage.insert(name, styles);
         self
     }
 
@@ -0,0 +1,40 @@
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io::{self, stdout};
+
+/// Puts the terminal in raw mode (capturing the mouse) and restores it when
+/// dropped, so a panic inside the render loop doesn't leave a broken terminal.
+pub struct TerminalGuard {
+    alternate_screen: bool,
+}
+
+impl TerminalGuard {
+    /// Enables the raw mode and the mouse capture, with `alternate_screen` the
+    /// UI is drawn in the alternate screen and the previous contents of the
+    /// terminal come back when the guard is dropped.
+    pub fn new(alternate_screen: bool) -> io::Result<Self> {
+        enable_raw_mode()?;
+        // from here on the terminal is restored even if the next steps fail
+        let guard = TerminalGuard { alternate_screen };
+        if alternate_screen {
+            execute!(stdout(), EnterAlternateScreen)?;
+        }
+        execute!(stdout(), EnableMouseCapture)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // errors are ignored, the guard can be dropped while panicking
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout(), DisableMouseCapture, Show);
+        if self.alternate_screen {
+            let _ = execute!(stdout(), LeaveAlternateScreen);
+        }
+    }
+}
@@ -1,15 +1,26 @@
-use std::collections::HashMap;
-use tui::{
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use log::warn;
+use std::{borrow::Borrow, collections::HashMap};
+use unicode_width::UnicodeWidthStr;
+
+use crate::backend::{
+    buffer::Buffer,
+    layout::Rect,
     style::{Color, Modifier, Style},
-    widgets::Borders,
+    widgets::{BorderType, Borders},
 };
 
-pub fn extract_attribute(data: HashMap<String, String>, attribute_name: &str) -> String {
-    let default_value = "".to_string();
-    let value = data.get(attribute_name).unwrap_or(&default_value);
-    String::from(value)
+/// Reads an attribute value, empty when it is not defined. The attributes can
+/// be passed by reference to avoid cloning the whole map.
+pub fn extract_attribute<M: Borrow<HashMap<String, String>>>(
+    data: M,
+    attribute_name: &str,
+) -> String {
+    let value = data.borrow().get(attribute_name);
+    value.cloned().unwrap_or_default()
 }
 
+/// Parses a modifier name, unknown names are reported and give no modifier.
 pub fn modifier_from_str(input: &str) -> Modifier {
     let input = input.to_lowercase();
     let input = input.as_str();
@@ -23,10 +34,15 @@ pub fn modifier_from_str(input: &str) -> Modifier {
         "slow_blink" => Modifier::SLOW_BLINK,
         "reversed" => Modifier::REVERSED,
         "hidden" => Modifier::HIDDEN,
-        _ => Modifier::DIM,
+        "normal" => Modifier::empty(),
+        _ => {
+            warn!("Unknown modifier \"{}\"", input);
+            Modifier::empty()
+        }
     }
 }
 
+/// Parses modifiers combined with `|`, e.g. `bold|italic`.
 pub fn modifiers_from_str(input: &str) -> Style {
     let values = input
         .to_lowercase()
@@ -63,6 +79,8 @@ pub fn color_from_str(input: &str) -> Color {
     }
 }
 
+/// Named color opposites (e.g. `"blue"` gives `"lightmagenta"`), other colors
+/// get `"black"` or `"white"` from `contrast_color_of`.
 pub fn contrast_color(input: &str) -> &str {
     let input = input.to_lowercase();
     let input = input.as_str();
@@ -83,10 +101,85 @@ pub fn contrast_color(input: &str) -> &str {
         "lightmagenta" => "magenta",
         "lightcyan" => "cyan",
         "white" => "black",
-        _ => "",
+        _ => match contrast_color_of(color_from_str(input)) {
+            Color::Black => "black",
+            Color::White => "white",
+            _ => "",
+        },
+    }
+}
+
+/// Picks black or white, the most readable over `color` by its luminance.
+/// `Color::Reset` has no known value and is returned as it is.
+pub fn contrast_color_of(color: Color) -> Color {
+    match color_to_rgb(color) {
+        Some((r, g, b)) => {
+            let luminance = 299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b);
+            if luminance > 128_000 {
+                Color::Black
+            } else {
+                Color::White
+            }
+        }
+        None => Color::Reset,
     }
 }
 
+/// RGB components of a color, named and indexed colors use the xterm palette.
+pub fn color_to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some((r, g, b)),
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Indexed(index) => index,
+    };
+    Some(match index {
+        0..=15 => ANSI[usize::from(index)],
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let cube = index - 16;
+            (level(cube / 36), level((cube / 6) % 6), level(cube % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    })
+}
+
 pub fn border_from_str(input: &str) -> Borders {
     match input {
         "all" => Borders::ALL,
@@ -108,3 +201,271 @@ pub fn borders_from_str(input: &str) -> Borders {
         });
     values
 }
+
+pub fn get_border_type(input: &str) -> BorderType {
+    let input = input.to_lowercase();
+    let input = input.as_str();
+    match input {
+        "plain" => BorderType::Plain,
+        "rounded" => BorderType::Rounded,
+        "double" => BorderType::Double,
+        "thick" => BorderType::Thick,
+        _ => {
+            warn!("Unknown border type '{}', using plain", input);
+            BorderType::Plain
+        }
+    }
+}
+
+/// Part of `area` inside `bounds`, an empty area at the corner of `bounds`
+/// when they don't overlap.
+pub fn clip_rect(area: Rect, bounds: Rect) -> Rect {
+    if area.intersects(bounds) {
+        area.intersection(bounds)
+    } else {
+        Rect::new(bounds.x, bounds.y, 0, 0)
+    }
+}
+
+/// Parses a comma separated list of numbers, skipping the empty or invalid
+/// entries.
+pub fn numbers_from_str(input: &str) -> Vec<u64> {
+    input
+        .split(',')
+        .filter_map(|value| value.trim().parse::<u64>().ok())
+        .collect()
+}
+
+/// Parses a `|` separated list of `label:value` pairs (e.g. `A:10|B:7`),
+/// skipping the malformed entries.
+pub fn labeled_values_from_str(input: &str) -> Vec<(String, u64)> {
+    input
+        .split('|')
+        .filter_map(|entry| {
+            let (label, value) = entry.split_once(':')?;
+            let value = value.trim().parse::<u64>().ok()?;
+            Some((label.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// Estimates the rows a text takes wrapped to `width` columns, words are not
+/// taken into account so a word wrap can take some more rows.
+pub fn text_height(text: &str, width: u16, trim: bool) -> usize {
+    let width = usize::from(width.max(1));
+    text.split('\n')
+        .map(|line| {
+            let line = if trim { line.trim() } else { line };
+            std::cmp::max(1, line.width().div_ceil(width))
+        })
+        .sum()
+}
+
+/// Wraps a text to `width` columns breaking lines on spaces, words longer
+/// than the width are split.
+pub fn wrap_text(text: &str, width: u16) -> Vec<String> {
+    let width = usize::from(width.max(1));
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let sep = usize::from(!line.is_empty());
+            if line.width() + sep + word.width() <= width {
+                if sep == 1 {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for ch in word.chars() {
+                if line.width() + ch.to_string().width() > width && !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(ch);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Shortens a text wider than `width` columns to `width - 1` columns plus
+/// `…`, wide characters (e.g. CJK) count their columns.
+pub fn truncate_with_ellipsis(text: &str, width: u16) -> String {
+    let width = usize::from(width);
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut res = String::new();
+    for ch in text.chars() {
+        if res.width() + ch.to_string().width() > width.saturating_sub(1) {
+            break;
+        }
+        res.push(ch);
+    }
+    if width > 0 {
+        res.push('…');
+    }
+    res
+}
+
+/// A key with its modifiers, characters are kept in lower case (an upper case
+/// character means `shift`).
+pub type KeyCombo = (KeyCode, KeyModifiers);
+
+/// Parses key combinations like `ctrl+s`, `alt+shift+x` or `f5`, returns
+/// `None` for unknown keys or modifiers.
+pub fn parse_key_combo(input: &str) -> Option<KeyCombo> {
+    let input = input.to_lowercase();
+    let mut parts: Vec<&str> = input.split('+').map(str::trim).collect();
+    let key = parts.pop()?;
+    let mut modifiers = KeyModifiers::NONE;
+    for part in parts {
+        modifiers |= match part {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+    }
+    let code = match key {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        "plus" => KeyCode::Char('+'),
+        _ if key.chars().count() == 1 => KeyCode::Char(key.chars().next()?),
+        _ => match key.strip_prefix('f').map(str::parse::<u8>) {
+            Some(Ok(number)) if (1..=12).contains(&number) => KeyCode::F(number),
+            _ => return None,
+        },
+    };
+    Some((code, modifiers))
+}
+
+/// Builds the combination of a key event, comparable with the ones returned
+/// by `parse_key_combo`.
+pub fn key_combo_from_event(event: &KeyEvent) -> KeyCombo {
+    match event.code {
+        KeyCode::Char(c) if c.is_uppercase() => (
+            KeyCode::Char(c.to_ascii_lowercase()),
+            event.modifiers | KeyModifiers::SHIFT,
+        ),
+        code => (code, event.modifiers),
+    }
+}
+
+fn ansi_color(color: Color, background: bool) -> String {
+    let offset = if background { 10 } else { 0 };
+    match color {
+        Color::Reset => format!("{}", 39 + offset),
+        Color::Black => format!("{}", 30 + offset),
+        Color::Red => format!("{}", 31 + offset),
+        Color::Green => format!("{}", 32 + offset),
+        Color::Yellow => format!("{}", 33 + offset),
+        Color::Blue => format!("{}", 34 + offset),
+        Color::Magenta => format!("{}", 35 + offset),
+        Color::Cyan => format!("{}", 36 + offset),
+        Color::Gray => format!("{}", 37 + offset),
+        Color::DarkGray => format!("{}", 90 + offset),
+        Color::LightRed => format!("{}", 91 + offset),
+        Color::LightGreen => format!("{}", 92 + offset),
+        Color::LightYellow => format!("{}", 93 + offset),
+        Color::LightBlue => format!("{}", 94 + offset),
+        Color::LightMagenta => format!("{}", 95 + offset),
+        Color::LightCyan => format!("{}", 96 + offset),
+        Color::White => format!("{}", 97 + offset),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", 38 + offset, r, g, b),
+        Color::Indexed(i) => format!("{};5;{}", 38 + offset, i),
+    }
+}
+
+/// SGR escape sequence for a cell style (colors and modifiers).
+fn ansi_style(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::RAPID_BLINK, "6"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::HIDDEN, "8"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    let mut codes = vec!["0".to_string(), ansi_color(fg, false), ansi_color(bg, true)];
+    for (flag, code) in modifiers {
+        if modifier.contains(flag) {
+            codes.push(code.to_string());
+        }
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// Dumps the cells of a buffer with their styles as ANSI escape codes, one
+/// line per row. Every line ends resetting the styles.
+pub fn buffer_to_ansi(buffer: &Buffer) -> String {
+    let width = usize::from(buffer.area.width).max(1);
+    let lines: Vec<String> = buffer
+        .content
+        .chunks(width)
+        .map(|cells| {
+            let mut line = String::new();
+            let mut skip: usize = 0;
+            let mut last_style = None;
+            for cell in cells {
+                // cells after a wide symbol are hidden by it
+                if skip == 0 {
+                    let style = (cell.fg, cell.bg, cell.modifier);
+                    if last_style != Some(style) {
+                        line.push_str(&ansi_style(cell.fg, cell.bg, cell.modifier));
+                        last_style = Some(style);
+                    }
+                    line.push_str(&cell.symbol);
+                }
+                skip = std::cmp::max(skip, cell.symbol.width()).saturating_sub(1);
+            }
+            line.push_str("\x1b[0m");
+            line
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Dumps the symbols of a buffer (no styles), one line per row without the
+/// trailing spaces.
+pub fn buffer_to_plain(buffer: &Buffer) -> String {
+    let width = usize::from(buffer.area.width).max(1);
+    let lines: Vec<String> = buffer
+        .content
+        .chunks(width)
+        .map(|cells| {
+            let mut line = String::new();
+            let mut skip: usize = 0;
+            for cell in cells {
+                // cells after a wide symbol are hidden by it
+                if skip == 0 {
+                    line.push_str(&cell.symbol);
+                }
+                skip = std::cmp::max(skip, cell.symbol.width()).saturating_sub(1);
+            }
+            line.trim_end().to_string()
+        })
+        .collect();
+    lines.join("\n")
+}
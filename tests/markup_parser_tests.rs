@@ -1,11 +1,32 @@
 #[cfg(test)]
 mod markup_parser {
+    use crossterm::event::{
+        Event as CEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+    };
+    use crossterm::terminal::is_raw_mode_enabled;
+    use std::collections::HashMap;
     use std::env::current_dir;
     use std::error::Error;
-    use tui::{backend::TestBackend, buffer::Buffer, layout::Rect, widgets::Block, Terminal};
+    use std::time::Duration;
+    use tui_markup_renderer::backend::{
+        backend::TestBackend,
+        buffer::Buffer,
+        layout::{Constraint, Rect},
+        style::{Color, Modifier, Style},
+        widgets::Block,
+        Terminal,
+    };
     use tui_markup_renderer::{
+        actions::IActionsStorage,
+        event_response::EventResponse,
+        markup_element::MarkupElement,
+        markup_error::MarkupError,
         markup_parser::MarkupParser,
+        state_value::StateValue,
         storage::{IRendererStorage, RendererStorage},
+        styles::IStylesStorage,
+        terminal_guard::TerminalGuard,
+        utils::{contrast_color, contrast_color_of, truncate_with_ellipsis},
     };
 
     // To catch panic use #[should_panic]
@@ -14,7 +35,7 @@ mod markup_parser {
     fn creation() -> Result<(), String> {
         let filepath = match current_dir() {
             Ok(exe_path) => format!("{}/tests/assets/creation_sample.tml", exe_path.display()),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
         let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
         assert_eq!(mp.path, filepath);
@@ -25,12 +46,23 @@ mod markup_parser {
     fn error_handling() {
         let filepath = match current_dir() {
             Ok(exe_path) => format!("{}/tests/assets/bad_sample.tml", exe_path.display()),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
         let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
         assert!(mp.failed);
         assert!(mp.error.is_some());
-        assert_eq!(mp.error.unwrap(), "Unexpected closing tag: header != title");
+        assert_eq!(
+            mp.error.unwrap(),
+            "line 4, col 11: Unexpected closing tag: header != title"
+        );
+        assert_eq!(
+            mp.markup_error,
+            Some(MarkupError::Parse {
+                line: 4,
+                column: 11,
+                message: "Unexpected closing tag: header != title".to_string(),
+            })
+        );
         // "Unexpected closing tag: header, expected title"
     }
 
@@ -38,7 +70,7 @@ mod markup_parser {
     fn complete_parsing() {
         let filepath = match current_dir() {
             Ok(exe_path) => format!("{}/tests/assets/real_sample.tml", exe_path.display()),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
         let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
         assert!(!mp.failed);
@@ -55,17 +87,20 @@ mod markup_parser {
                 "{}/tests/assets/sample_single_block.tml",
                 exe_path.display()
             ),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
 
         let backend = TestBackend::new(15, 3);
         let mut store = RendererStorage::new();
         let b = String::from("block");
-        store.add_renderer(&b, |f| {
+        store.add_renderer(&b, |node, area, _state, f| {
             let border = MarkupParser::<TestBackend>::get_border("all");
-            let block = Block::default().title("( Hi! )").borders(border);
-            let area = Rect::new(0, 0, 15, 3);
+            let title = node.attributes.get("title").cloned().unwrap_or_default();
+            let block = Block::default()
+                .title(format!("( {} )", title))
+                .borders(border);
             f.render_widget(block, area);
+            Some(())
         });
 
         let mut mp = MarkupParser::new(filepath.clone(), Some(store), None);
@@ -77,7 +112,7 @@ mod markup_parser {
         })?;
 
         let expected = Buffer::with_lines(vec![
-            "┌( Hi! )──────┐",
+            "┌( BTitle )───┐",
             "│             │",
             "└─────────────┘",
         ]);
@@ -93,7 +128,7 @@ mod markup_parser {
                 "{}/tests/assets/sample_couple_blocks.tml",
                 exe_path.display()
             ),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
         let mut mp = MarkupParser::new(filepath.clone(), None, None);
 
@@ -128,7 +163,7 @@ mod markup_parser {
     fn render_check3() -> Result<(), Box<dyn Error>> {
         let filepath = match current_dir() {
             Ok(exe_path) => format!("{}/tests/assets/sample_units.tml", exe_path.display()),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
         let mut mp = MarkupParser::new(filepath.clone(), None, None);
 
@@ -163,7 +198,7 @@ mod markup_parser {
                 "{}/tests/assets/sample_nested_blocks.tml",
                 exe_path.display()
             ),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
         let mut mp = MarkupParser::new(filepath.clone(), None, None);
 
@@ -198,7 +233,7 @@ mod markup_parser {
     fn render_check5() -> Result<(), Box<dyn Error>> {
         let filepath = match current_dir() {
             Ok(exe_path) => format!("{}/tests/assets/sample_widgets_1.tml", exe_path.display()),
-            Err(_e) => format!(""),
+            Err(_e) => String::new(),
         };
         let mut mp = MarkupParser::new(filepath.clone(), None, None);
 
@@ -225,4 +260,2582 @@ mod markup_parser {
 
         Ok(())
     }
+
+    #[test]
+    fn dialog_buttons_actions() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_dialog_actions.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let state = HashMap::from([("showDialog".to_string(), "true".into())]);
+        let mut mp = MarkupParser::new(filepath.clone(), None, Some(state));
+
+        let backend = TestBackend::new(60, 40);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+
+        let actions: Vec<(String, String)> = mp
+            .indexed_elements
+            .iter()
+            .map(|x| (x.id.clone(), x.attributes.get("action").unwrap().clone()))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("dlg_btn_Save".to_string(), "save_form".to_string()),
+                (
+                    "dlg_btn_Discard".to_string(),
+                    "on_dlg_btn_Discard".to_string()
+                ),
+                ("dlg_btn_Cancel".to_string(), "__close_dialog".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn close_dialog_action() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_dialog_actions.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let state = HashMap::from([("showDialog".to_string(), "true".into())]);
+        let mut mp = MarkupParser::new(filepath.clone(), None, Some(state));
+
+        let backend = TestBackend::new(60, 40);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+
+        let cancel = mp.indexed_elements.last().cloned();
+        let response = mp
+            .actions
+            .execute("__close_dialog".to_string(), mp.state.clone(), cancel);
+        match response {
+            Some(EventResponse::STATE(state)) => {
+                assert_eq!(state.get("showDialog").unwrap(), "false");
+            }
+            _ => panic!("__close_dialog must return a new state"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn unhandled_keys() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.add_action("do_first", |_state, _node| EventResponse::NOOP)
+            .on_unhandled_key(|key_event, state| {
+                state.insert(
+                    "unhandled".to_string(),
+                    format!("{:?}", key_event.code).into(),
+                );
+                EventResponse::NOOP
+            });
+
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        mp.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!mp.state.contains_key("unhandled"));
+
+        mp.handle_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert_eq!(mp.state.get("unhandled").unwrap(), "Char('x')");
+    }
+
+    #[test]
+    fn arrows_navigation() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(!mp.focused_consumes_arrows());
+
+        mp.handle_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        assert_eq!(mp.current, 0);
+        mp.handle_key(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE));
+        assert_eq!(mp.current, 1);
+        mp.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        assert_eq!(mp.current, 0);
+        mp.handle_key(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE));
+        assert_eq!(mp.current, -1);
+    }
+
+    #[test]
+    fn relative_min_constraint() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_relative_min.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let backend = TestBackend::new(20, 6);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+
+        let expected = Buffer::with_lines(vec![
+            "                    ",
+            " ┌A──────────┐┌B──┐ ",
+            "                    ",
+            "                    ",
+            " ┌A─┐┌B───────────┐ ",
+            "                    ",
+        ]);
+        terminal.backend().assert_buffer(&expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn render_skeleton() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_skeleton.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let backend = TestBackend::new(10, 4);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+
+        let mut expected =
+            Buffer::with_lines(vec!["┌Data────┐", "│░░░░░░░░│", "│░░░░░   │", "└────────┘"]);
+        expected.set_style(Rect::new(1, 1, 8, 2), Style::default().fg(Color::DarkGray));
+        terminal.backend().assert_buffer(&expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn esc_closes_dialog() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_dialog_actions.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let state = HashMap::from([("showDialog".to_string(), "true".into())]);
+        let mut mp = MarkupParser::new(filepath.clone(), None, Some(state));
+
+        let backend = TestBackend::new(60, 40);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+        assert_eq!(mp.contexts.len(), 1);
+
+        mp.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(mp.contexts.is_empty());
+        assert!(mp.indexed_elements.is_empty());
+        assert_eq!(mp.state.get("showDialog").unwrap(), "false");
+
+        Ok(())
+    }
+
+    #[test]
+    fn navigation_cycle() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let len = mp.indexed_elements.len();
+
+        let forward: Vec<i32> = (0..=len)
+            .map(|_| {
+                mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+                mp.current
+            })
+            .collect();
+        assert_eq!(forward, vec![0, 1, -1]);
+
+        let backward: Vec<i32> = (0..=len)
+            .map(|_| {
+                mp.handle_key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE));
+                mp.current
+            })
+            .collect();
+        assert_eq!(backward, vec![1, 0, -1]);
+    }
+
+    #[test]
+    fn dynamic_tabs() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(mp.add_tab("tabs-cmp", "tab3", "Tab 3"));
+        assert!(!mp.add_tab("tabs-cmp", "tab3", "Tab 3"));
+        let ids: Vec<String> = mp.indexed_elements.iter().map(|x| x.id.clone()).collect();
+        assert_eq!(ids, vec!["tab1", "tab2", "tab3"]);
+
+        mp.handle_key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE));
+        mp.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(mp.state.get("tabs-cmp:index").unwrap(), "tab3");
+
+        assert!(mp.remove_tab("tabs-cmp", "tab3"));
+        assert_eq!(mp.state.get("tabs-cmp:index").unwrap(), "tab2");
+        assert_eq!(mp.indexed_elements.len(), 2);
+        assert!(mp.find_element("tab3_content").is_none());
+    }
+
+    #[test]
+    fn render_plain() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_couple_blocks.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let expected = [
+            "┌Nav─────┐",
+            "│        │",
+            "└────────┘",
+            "┌Body────┐",
+            "│        │",
+            "│        │",
+            "│        │",
+            "│        │",
+            "│        │",
+            "└────────┘",
+        ];
+        assert_eq!(mp.render_plain(10, 10), expected.join("\n"));
+    }
+
+    #[test]
+    fn render_to_string() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_border_style.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let plain = mp.render_to_string(6, 3, false);
+        assert_eq!(plain, mp.render_plain(6, 3));
+
+        let ansi = mp.render_to_string(6, 3, true);
+        let lines: Vec<&str> = ansi.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "\x1b[0;90;49m┌────┐\x1b[0m");
+        assert_eq!(
+            lines[1],
+            "\x1b[0;90;49m│\x1b[0;97;49m    \x1b[0;90;49m│\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn vertical_tabs() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_vertical_tabs.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+        let expected = [
+            "╭───────│",
+            "│ Tab 1 │",
+            "╰───────│ Content 1",
+            "╭───────│",
+            "│ Tab 2 │",
+            "╰───────│",
+            "        │",
+            "        │",
+            "        │",
+            "        │",
+        ];
+        assert_eq!(mp.render_plain(30, 10), expected.join("\n"));
+    }
+
+    #[test]
+    fn nested_tabs() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_nested_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+        let screen = mp.render_plain(40, 8);
+        let expected = [
+            " ╭───────╮╭───────╮",
+            " │ Tab 1 ││ Tab 2 │",
+            "────────────────────────────────────────",
+            "",
+            "  ╭───────╮╭───────╮",
+            "  │ In 1  ││ In 2  │",
+            " ──────────────────────────────────────",
+            "",
+        ];
+        assert_eq!(screen, expected.join("\n"));
+
+        let focus_cycle = |mp: &mut MarkupParser<TestBackend>| {
+            let mut ids: Vec<String> = vec![];
+            for _ in 0..=mp.indexed_elements.len() {
+                mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+                if mp.current > -1 {
+                    ids.push(mp.indexed_elements[mp.current as usize].id.clone());
+                }
+            }
+            ids.sort();
+            ids.dedup();
+            ids
+        };
+        assert_eq!(focus_cycle(&mut mp), vec!["in1", "in2", "tab1", "tab2"]);
+
+        mp.state.insert("outer:index".to_string(), "tab2".into());
+        mp.render_plain(40, 8);
+        assert_eq!(focus_cycle(&mut mp), vec!["tab1", "tab2"]);
+    }
+
+    #[test]
+    fn scrollable_paragraph() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_scrollable.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+
+        assert!(mp.render_plain(10, 5).contains("line 1"));
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(mp.current, 0);
+
+        mp.handle_key(down);
+        assert_eq!(mp.current, 0);
+        assert_eq!(mp.state.get("log:scroll"), Some(&StateValue::Int(1)));
+        let screen = mp.render_plain(10, 5);
+        assert!(screen.contains("line 2"));
+        assert!(!screen.contains("line 1"));
+
+        for _ in 0..10 {
+            mp.handle_key(down);
+        }
+        assert_eq!(mp.state.get("log:scroll"), Some(&StateValue::Int(3)));
+        for _ in 0..10 {
+            mp.handle_key(up);
+        }
+        assert_eq!(mp.state.get("log:scroll"), Some(&StateValue::Int(0)));
+    }
+
+    #[test]
+    fn border_types() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_border_types.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let expected = [
+            "┏A━━━┓",
+            "┃    ┃",
+            "┗━━━━┛",
+            "╔B═══╗",
+            "║    ║",
+            "╚════╝",
+            "┌C───┐",
+            "│    │",
+            "└────┘",
+        ];
+        assert_eq!(mp.render_plain(6, 9), expected.join("\n"));
+    }
+
+    #[test]
+    fn border_style() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_border_style.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let backend = TestBackend::new(6, 3);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+
+        let mut expected = Buffer::with_lines(vec!["┌────┐", "│    │", "└────┘"]);
+        expected.set_style(Rect::new(0, 0, 6, 3), Style::default().fg(Color::DarkGray));
+        expected.set_style(Rect::new(1, 1, 4, 1), Style::default().fg(Color::White));
+        terminal.backend().assert_buffer(&expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn disabled_button() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_disabled.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+        let ids: Vec<String> = mp.indexed_elements.iter().map(|x| x.id.clone()).collect();
+        assert_eq!(ids, vec!["btn_first"]);
+
+        let backend = TestBackend::new(40, 3);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+
+        let buffer = terminal.backend().buffer();
+        let dimmed = |symbol: &str| {
+            let cell = buffer.content.iter().find(|cell| cell.symbol == symbol);
+            cell.unwrap().modifier.contains(Modifier::DIM)
+        };
+        assert!(dimmed("S"));
+        assert!(!dimmed("F"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn render_gauge() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_gauge.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let state = HashMap::from([("download.percent".to_string(), "50".into())]);
+        let mut mp = MarkupParser::new(filepath.clone(), None, Some(state));
+        assert_eq!(mp.render_plain(20, 3).trim(), "Down 50%");
+
+        for (value, expected) in [("250", "Down 100%"), ("-3", "Down 0%"), ("abc", "Down 0%")] {
+            mp.state
+                .insert("download.percent".to_string(), value.into());
+            assert!(mp.render_plain(20, 3).contains(expected));
+        }
+    }
+
+    #[test]
+    fn render_sparkline() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_sparkline.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let state = HashMap::from([("cpu.history".to_string(), "1,,4,x, 8,2".into())]);
+        let mut mp = MarkupParser::new(filepath.clone(), None, Some(state));
+        let screen = mp.render_plain(6, 3);
+        assert_eq!(screen.lines().last(), Some("▂██▄"));
+    }
+
+    #[test]
+    fn render_barchart() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_barchart.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let state = HashMap::from([("votes".to_string(), "A:10|bad|B:x|B:5| C : 0".into())]);
+        let mut mp = MarkupParser::new(filepath.clone(), None, Some(state));
+        let screen = mp.render_plain(14, 6);
+        // the malformed entries are skipped
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[3..], ["███ ███", "10█ █5█", "A   B   C"]);
+    }
+
+    #[test]
+    fn negative_modifiers() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_negative_modifiers.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let backend = TestBackend::new(10, 3);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            let w = mp.render_ui(f);
+            w.unwrap_or(false);
+        })?;
+
+        let buffer = terminal.backend().buffer();
+        let modifier = |symbol: &str| {
+            let cell = buffer.content.iter().find(|cell| cell.symbol == symbol);
+            cell.unwrap().modifier
+        };
+        assert_eq!(modifier("B"), Modifier::BOLD | Modifier::UNDERLINED);
+        assert_eq!(modifier("P"), Modifier::UNDERLINED);
+
+        Ok(())
+    }
+
+    #[test]
+    fn layout_cache() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let fresh = |width: u16, height: u16| {
+            MarkupParser::new(filepath.clone(), None, None).render_plain(width, height)
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        // a resize computes the layout again
+        assert_eq!(mp.render_plain(30, 8), fresh(30, 8));
+        assert_eq!(mp.render_plain(40, 6), fresh(40, 6));
+
+        // a tree change computes the layout again
+        assert!(mp.add_tab("tabs-cmp", "tab9", "Extra"));
+        assert!(mp.render_plain(40, 6).contains("Extra"));
+    }
+
+    #[test]
+    fn mouse_scroll() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_scrollable.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+        let wheel = |kind: MouseEventKind, column: u16, row: u16| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        mp.render_plain(10, 5);
+
+        mp.handle_mouse(wheel(MouseEventKind::ScrollDown, 2, 1));
+        mp.handle_mouse(wheel(MouseEventKind::ScrollDown, 2, 1));
+        assert_eq!(mp.state.get("log:scroll"), Some(&StateValue::Int(2)));
+        mp.handle_mouse(wheel(MouseEventKind::ScrollUp, 2, 1));
+        assert_eq!(mp.state.get("log:scroll"), Some(&StateValue::Int(1)));
+
+        // the footer isn't scrollable
+        mp.handle_mouse(wheel(MouseEventKind::ScrollDown, 2, 4));
+        assert_eq!(mp.state.get("log:scroll"), Some(&StateValue::Int(1)));
+        assert_eq!(mp.current, -1);
+    }
+
+    #[test]
+    fn repeat_from_state() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_repeat.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let state = HashMap::from([("todos".to_string(), "milk, eggs,bread".into())]);
+        let mut mp = MarkupParser::new(filepath.clone(), None, Some(state));
+        // paragraphs keep their first row for the title
+        assert_eq!(mp.render_plain(10, 6), "\n- milk\n\n- eggs\n\n- bread");
+
+        mp.state.insert("todos".to_string(), "tea".into());
+        assert_eq!(mp.render_plain(10, 6), "\n- tea\n\n\n\n");
+
+        mp.state.insert("todos".to_string(), "".into());
+        assert_eq!(mp.render_plain(10, 6), "\n\n\n\n\n");
+    }
+
+    #[test]
+    fn conditional_rendering() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_conditional.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let expected = [
+            "┌Simple──┐",
+            "│        │",
+            "└────────┘",
+            "┌Body────┐",
+            "│        │",
+            "└────────┘",
+        ];
+        assert_eq!(mp.render_plain(10, 6), expected.join("\n"));
+
+        mp.state.insert("showAdvanced".to_string(), "true".into());
+        let expected = [
+            "┌Adv─────┐",
+            "│        │",
+            "└────────┘",
+            "┌Body────┐",
+            "│        │",
+            "└────────┘",
+        ];
+        assert_eq!(mp.render_plain(10, 6), expected.join("\n"));
+    }
+
+    #[test]
+    fn include_markup() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_include.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+        assert!(mp.error.is_none());
+
+        let expected = [
+            "┌Nav─────┐",
+            "│        │",
+            "└────────┘",
+            "┌Body────┐",
+            "│        │",
+            "└────────┘",
+        ];
+        assert_eq!(mp.render_plain(10, 6), expected.join("\n"));
+    }
+
+    #[test]
+    fn include_cycle() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_include_cycle.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(mp.failed);
+        assert!(mp.error.unwrap().starts_with("Include cycle:"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use std::{cell::RefCell, rc::Rc};
+        use tui_markup_renderer::markup_element::MarkupElement;
+
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let root = mp.root.clone().unwrap();
+        let json = serde_json::to_value(&*root.as_ref().borrow()).unwrap();
+        assert!(json.get("parent_node").is_none());
+
+        let copy: MarkupElement = serde_json::from_value(json.clone()).unwrap();
+        let copy = Rc::new(RefCell::new(copy));
+        MarkupElement::rebuild_parents_of(&copy);
+        assert_eq!(
+            serde_json::to_value(&*copy.as_ref().borrow()).unwrap(),
+            json
+        );
+
+        let copy = copy.as_ref().borrow();
+        let block = copy.children[0].as_ref().borrow();
+        let tabs = block.children[0].as_ref().borrow();
+        assert_eq!(tabs.deep, 2);
+        let parent = tabs.parent_node.clone().unwrap();
+        assert_eq!(parent.as_ref().borrow().id, "blk1");
+    }
+
+    #[test]
+    fn unknown_names() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_unknown_names.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(!mp.failed);
+        assert_eq!(
+            mp.warnings(),
+            [
+                "Unknown element <containr>",
+                "Unknown attribute \"colr\" in <p>",
+            ]
+        );
+
+        mp.set_strict(true);
+        assert!(mp.failed);
+        assert_eq!(
+            mp.error.clone().unwrap(),
+            "Unknown element <containr>; Unknown attribute \"colr\" in <p>"
+        );
+
+        mp.set_strict(false);
+        assert!(!mp.failed);
+        assert!(mp.error.is_none());
+
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/real_sample.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_strict(true);
+        assert!(!mp.failed);
+        assert!(mp.warnings().is_empty());
+    }
+
+    #[test]
+    fn state_accessors() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_gauge.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_state("download.percent", "30")
+            .set_state("other", "1");
+        assert_eq!(mp.get_state("download.percent").unwrap(), "30");
+        assert_eq!(mp.render_plain(20, 3).trim(), "Down 30%");
+
+        assert_eq!(mp.remove_state("download.percent").unwrap(), "30");
+        assert!(mp.get_state("download.percent").is_none());
+        assert_eq!(mp.render_plain(20, 3).trim(), "Down 0%");
+
+        mp.clear_state();
+        assert!(mp.state.is_empty());
+    }
+
+    #[test]
+    fn typed_state() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_gauge.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_state("download.percent", 42);
+        assert_eq!(mp.render_plain(20, 3).trim(), "Down 42%");
+
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_repeat.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_state("todos", vec!["milk, cold".to_string(), "tea".to_string()]);
+        assert_eq!(mp.render_plain(12, 4), "\n- milk, cold\n\n- tea");
+
+        let value = StateValue::from(true);
+        assert_eq!(value, "true");
+        assert_eq!(value.as_bool(), Some(true));
+        assert_eq!(StateValue::from("12").as_int(), Some(12));
+        assert_eq!(StateValue::from("a|b").as_list("|"), ["a", "b"]);
+        assert_eq!(StateValue::from(vec!["a".to_string()]).to_string(), "a");
+    }
+
+    #[test]
+    fn focused_id_and_dispatch() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.add_action("remember", |state, node| {
+            let mut state = state;
+            let id = node.map(|x| x.id).unwrap_or_default();
+            state.insert("last".to_string(), id.into());
+            EventResponse::STATE(state)
+        });
+        assert!(mp.focused_id().is_none());
+        assert!(matches!(mp.dispatch("remember"), EventResponse::STATE(_)));
+        assert_eq!(mp.get_state("last").unwrap(), "");
+
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        let focused = mp.focused_id().unwrap();
+        mp.dispatch("remember");
+        assert_eq!(mp.get_state("last").unwrap(), &focused);
+
+        assert!(matches!(mp.dispatch("unknown"), EventResponse::NOOP));
+    }
+
+    #[test]
+    fn chained_actions() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_chained_actions.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.add_action("validate", |state, _node| {
+            let mut state = state;
+            state.insert("valid".to_string(), true.into());
+            EventResponse::STATE(state)
+        })
+        .add_action("submit", |state, _node| {
+            let mut state = state;
+            let valid = state.get("valid").cloned().unwrap_or_default();
+            state.insert("submitted".to_string(), valid);
+            EventResponse::STATE(state)
+        })
+        .add_action("quit", |_state, _node| EventResponse::QUIT)
+        .add_action("never", |state, _node| {
+            let mut state = state;
+            state.insert("never".to_string(), true.into());
+            EventResponse::STATE(state)
+        });
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+
+        mp.handle_key(tab);
+        assert!(matches!(mp.handle_key(enter), EventResponse::STATE(_)));
+        assert_eq!(mp.get_state("submitted").unwrap(), "true");
+
+        mp.handle_key(tab);
+        assert!(matches!(mp.handle_key(enter), EventResponse::QUIT));
+        assert!(mp.get_state("never").is_none());
+    }
+
+    #[test]
+    fn preserve_whitespace() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_preserve_whitespace.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let code = mp.find_element("code").unwrap();
+        assert_eq!(code.text.unwrap(), "fn main() {\n    run();\n}");
+
+        // paragraphs keep their first row for the title
+        let expected = ["", "fn main() {", "    run();", "}"];
+        assert_eq!(mp.render_plain(20, 4), expected.join("\n"));
+    }
+
+    #[test]
+    fn compute_layout() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_units.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let layout = mp.compute_layout(40, 20);
+        let area_of = |id: &str| {
+            layout
+                .iter()
+                .find(|(_, elm_id)| elm_id.eq(id))
+                .map(|(area, _)| *area)
+                .unwrap()
+        };
+        assert_eq!(area_of("nav_container"), Rect::new(0, 0, 40, 4));
+        assert_eq!(area_of("body_container"), Rect::new(0, 4, 40, 16));
+        assert_eq!(area_of("ats_block"), Rect::new(0, 4, 5, 16));
+        assert_eq!(area_of("inner_block"), Rect::new(6, 5, 5, 14));
+        assert!(mp.compute_layout(40, 20).eq(&layout));
+    }
+
+    #[test]
+    fn stacked_dialogs() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_two_dialogs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let ids = |mp: &MarkupParser<TestBackend>| -> Vec<String> {
+            mp.indexed_elements.iter().map(|x| x.id.clone()).collect()
+        };
+        let show = |mp: &mut MarkupParser<TestBackend>, key: &str, value: bool| {
+            mp.set_state(key, value);
+            mp.render_plain(60, 40);
+        };
+
+        show(&mut mp, "showFirst", true);
+        show(&mut mp, "showSecond", true);
+        // rendering again doesn't open the dialogs twice
+        show(&mut mp, "showSecond", true);
+        assert_eq!(mp.contexts.len(), 2);
+        assert_eq!(ids(&mp), ["second_btn_Yes", "second_btn_No"]);
+
+        show(&mut mp, "showSecond", false);
+        assert_eq!(ids(&mp), ["first_btn_Ok", "first_btn_Cancel"]);
+        show(&mut mp, "showFirst", false);
+        assert!(mp.contexts.is_empty());
+        assert_eq!(ids(&mp), ["btn_main"]);
+
+        // closing the dialog under the top one
+        show(&mut mp, "showFirst", true);
+        show(&mut mp, "showSecond", true);
+        show(&mut mp, "showFirst", false);
+        assert_eq!(mp.contexts.len(), 1);
+        assert_eq!(ids(&mp), ["second_btn_Yes", "second_btn_No"]);
+        show(&mut mp, "showSecond", false);
+        assert!(mp.contexts.is_empty());
+        assert_eq!(ids(&mp), ["btn_main"]);
+    }
+
+    #[test]
+    fn min_max_height() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_min_max_height.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let area_of = |height: u16, id: &str| {
+            mp.compute_layout(20, height)
+                .into_iter()
+                .find(|(_, elm_id)| elm_id.eq(id))
+                .map(|(area, _)| area)
+                .unwrap()
+        };
+        assert_eq!(area_of(20, "panel").height, 6);
+        assert_eq!(area_of(10, "panel").height, 5);
+        assert_eq!(area_of(10, "body"), Rect::new(0, 5, 20, 5));
+        assert_eq!(area_of(40, "panel").height, 8);
+        assert_eq!(area_of(40, "body"), Rect::new(0, 8, 20, 32));
+    }
+
+    #[test]
+    fn render_divider() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_divider.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let expected = ["", "", "─────────", "", "     ║", "     ║", ""];
+        assert_eq!(mp.render_plain(9, 7), expected.join("\n"));
+
+        let backend = TestBackend::new(9, 7);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        let cell = terminal.backend().buffer().get(3, 2);
+        assert_eq!(cell.fg, Color::Red);
+        Ok(())
+    }
+
+    #[test]
+    fn tabs_header_height() {
+        let area_of = |asset: &str, id: &str| {
+            let filepath = match current_dir() {
+                Ok(exe_path) => format!("{}/tests/assets/{}", exe_path.display(), asset),
+                Err(_e) => String::new(),
+            };
+            let mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+            mp.compute_layout(40, 40)
+                .into_iter()
+                .find(|(_, elm_id)| elm_id.eq(id))
+                .map(|(area, _)| area)
+                .unwrap()
+        };
+        let body = area_of("sample_tabs.tml", "t-body");
+        // the content fills the body, without a gap below the header
+        assert_eq!(area_of("sample_tabs.tml", "ctt-1"), body);
+
+        let taller_body = area_of("sample_tabs_header_height.tml", "t-body");
+        assert_eq!(taller_body.y, body.y + 1);
+        assert_eq!(taller_body.bottom(), body.bottom());
+    }
+
+    #[test]
+    fn active_tab_styles() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tab_styles.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend)?;
+        // the first render selects the first tab
+        for _ in 0..2 {
+            terminal.draw(|f| {
+                mp.render_ui(f).unwrap_or(false);
+            })?;
+        }
+        let buffer = terminal.backend().buffer();
+        let active = buffer.get(5, 1);
+        let inactive = buffer.get(14, 1);
+        assert_eq!(
+            (active.symbol.as_str(), inactive.symbol.as_str()),
+            ("a", "a")
+        );
+        assert_eq!(active.fg, Color::Red);
+        assert!(active.modifier.contains(Modifier::BOLD));
+        assert_eq!(inactive.fg, Color::DarkGray);
+        assert_eq!(inactive.bg, Color::Blue);
+        assert!(!inactive.modifier.contains(Modifier::BOLD));
+        Ok(())
+    }
+
+    #[test]
+    fn render_spinner() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_spinner.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::new(filepath.clone(), None, None);
+
+        let backend = TestBackend::new(12, 6);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        terminal.backend().assert_buffer(&Buffer::with_lines(vec![
+            "┌Data──────┐",
+            "│          │",
+            "└──────────┘",
+            "┌──────────┐",
+            "│▁         │",
+            "└──────────┘",
+        ]));
+
+        mp.set_state("loading", "true");
+        mp.tick();
+        mp.tick();
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        terminal.backend().assert_buffer(&Buffer::with_lines(vec![
+            "┌Data──────┐",
+            "│⠹ Loading │",
+            "└──────────┘",
+            "┌──────────┐",
+            "│▃         │",
+            "└──────────┘",
+        ]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn keybindings() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_keybindings.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert_eq!(mp.warnings(), vec!["Unknown key binding \"ctrl+nope\""]);
+        mp.add_action("save", |state, _node| {
+            let mut state = state;
+            state.insert("saved".to_string(), true.into());
+            EventResponse::STATE(state)
+        });
+        mp.add_action("reload", |state, _node| {
+            let mut state = state;
+            let count = state
+                .get("reloads")
+                .and_then(StateValue::as_int)
+                .unwrap_or(0);
+            state.insert("reloads".to_string(), (count + 1).into());
+            EventResponse::STATE(state)
+        });
+
+        mp.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE));
+        assert_eq!(mp.get_state("saved"), None);
+        mp.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert_eq!(mp.get_state("saved"), Some(&StateValue::Bool(true)));
+
+        mp.handle_key(KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(mp.get_state("reloads"), Some(&StateValue::Int(1)));
+        Ok(())
+    }
+
+    #[test]
+    fn dialog_buttons_focus() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_dialog_default.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.add_action("answer", |state, node| {
+            let mut state = state;
+            let answer = node.and_then(|x| x.text).unwrap_or_default();
+            state.insert("answer".to_string(), answer.into());
+            EventResponse::STATE(state)
+        });
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE);
+
+        mp.set_state("showDialog", true);
+        mp.render_plain(60, 40);
+        // the default button is focused when the dialog opens
+        assert_eq!(mp.focused_id().as_deref(), Some("dlg_btn_No"));
+        mp.handle_key(tab);
+        assert_eq!(mp.focused_id().as_deref(), Some("dlg_btn_Cancel"));
+        // the focus cycles between the buttons
+        mp.handle_key(tab);
+        assert_eq!(mp.focused_id().as_deref(), Some("dlg_btn_Yes"));
+        mp.handle_key(back_tab);
+        assert_eq!(mp.focused_id().as_deref(), Some("dlg_btn_Cancel"));
+        mp.handle_key(tab);
+        mp.handle_key(tab);
+        mp.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(mp.get_state("answer").unwrap(), "No");
+
+        // without a default button the first one gets the focus
+        mp.set_state("showDialog", false);
+        mp.render_plain(60, 40);
+        assert_eq!(mp.focused_id(), None);
+        let mut mp = MarkupParser::<TestBackend>::new(
+            filepath.replace("sample_dialog_default", "sample_two_dialogs"),
+            None,
+            None,
+        );
+        mp.set_state("showFirst", true);
+        mp.render_plain(60, 40);
+        assert_eq!(mp.focused_id().as_deref(), Some("first_btn_Ok"));
+    }
+
+    #[test]
+    fn vertical_alignment() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_valign.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let expected = [
+            "",
+            " ┌────────┐  ┌────────┐",
+            " │        │  │        │",
+            " │  Save  │  │        │",
+            " │changes?│  │        │",
+            " │        │  │Bottom  │",
+            " └────────┘  └────────┘",
+            "",
+        ];
+        assert_eq!(mp.render_plain(24, 8), expected.join("\n"));
+    }
+
+    #[test]
+    fn percentages_over_100() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_percentages.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(mp.warnings().is_empty());
+        let heights: HashMap<String, u16> = mp
+            .compute_layout(20, 22)
+            .into_iter()
+            .map(|(area, id)| (id, area.height))
+            .collect();
+        assert_eq!(
+            mp.warnings(),
+            vec![
+                "Percentages of layout #clipped sum 140%",
+                "Percentages of layout #scaled sum 140%",
+            ]
+        );
+        // the last child is clipped
+        assert_eq!((heights["top_a"], heights["bottom_a"]), (14, 2));
+        // normalize="true" scales them to 57% and 42%
+        assert_eq!((heights["top_b"], heights["bottom_b"]), (9, 7));
+        // the warnings aren't repeated on every layout pass
+        mp.compute_layout(20, 22);
+        assert_eq!(mp.warnings().len(), 2);
+    }
+
+    #[test]
+    fn fill_constraint() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_fill_constraint.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let areas: HashMap<String, Rect> = mp
+            .compute_layout(10, 20)
+            .into_iter()
+            .map(|(area, id)| (id, area))
+            .collect();
+        assert_eq!(areas["header"], Rect::new(0, 0, 10, 3));
+        assert_eq!(areas["body"], Rect::new(0, 3, 10, 15));
+        assert_eq!(areas["footer"], Rect::new(0, 18, 10, 2));
+    }
+
+    #[test]
+    fn block_footer() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_footer.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let backend = TestBackend::new(12, 5);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        // the footer is clipped between the corners, without bottom border
+        // there is no footer
+        let mut expected = Buffer::with_lines(vec![
+            "┌Files─────┐",
+            "│          │",
+            "└Press q to┘",
+            "────────────",
+            "            ",
+        ]);
+        expected.set_style(Rect::new(0, 0, 12, 3), Style::default().fg(Color::Blue));
+        expected.set_style(Rect::new(1, 1, 10, 1), Style::default().fg(Color::Reset));
+        terminal.backend().assert_buffer(&expected);
+        Ok(())
+    }
+
+    #[test]
+    fn radio_group() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_radio_group.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        // the group is a single focus stop
+        let ids: Vec<String> = mp.indexed_elements.iter().map(|x| x.id.clone()).collect();
+        assert_eq!(ids, ["size", "btn_ok"]);
+        assert!(mp.render_plain(16, 12).contains("│(•) Small   │"));
+
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        mp.handle_key(down);
+        mp.handle_key(down);
+        mp.handle_key(down);
+        assert_eq!(mp.focused_id().as_deref(), Some("size"));
+        assert_eq!(mp.get_state("form.size").unwrap(), "l");
+        mp.handle_key(up);
+        assert_eq!(mp.get_state("form.size").unwrap(), "m");
+        let lines = [
+            " ┌Size────────┐",
+            " │( ) Small   │",
+            " │(•) Medium  │",
+            " │( ) Large   │",
+            " └────────────┘",
+        ];
+        assert!(mp.render_plain(16, 12).contains(&lines.join("\n")));
+    }
+
+    #[test]
+    fn action_params() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_action_params.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(mp.warnings().is_empty());
+        mp.add_action("set_flag", |state, node| {
+            let mut state = state;
+            let params = node.map(|x| x.params()).unwrap_or_default();
+            state.insert(params["key"].clone(), params["value"].as_str().into());
+            EventResponse::STATE(state)
+        });
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+
+        mp.handle_key(tab);
+        mp.handle_key(enter);
+        assert_eq!(mp.get_state("darkMode").unwrap(), "true");
+        mp.handle_key(tab);
+        mp.handle_key(enter);
+        assert_eq!(mp.get_state("darkMode").unwrap(), "false");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn save_and_load_state() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let state_path = std::env::temp_dir().join("tui_markup_saved_state.json");
+        let state_path = state_path.to_str().unwrap();
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_state("tabs-cmp:index", "tab2")
+            .set_state("count", 3)
+            .set_state("dark", true);
+        mp.save_state(state_path).unwrap();
+
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_state("other", "kept");
+        mp.load_state(state_path).unwrap();
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+        assert_eq!(mp.get_state("count"), Some(&StateValue::Int(3)));
+        assert_eq!(mp.get_state("dark"), Some(&StateValue::Bool(true)));
+        assert_eq!(mp.get_state("other").unwrap(), "kept");
+
+        // a missing file does nothing, an invalid one is an error
+        std::fs::remove_file(state_path).unwrap();
+        assert_eq!(mp.load_state(state_path), Ok(()));
+        std::fs::write(state_path, "{ not json").unwrap();
+        assert!(matches!(
+            mp.load_state(state_path),
+            Err(MarkupError::State(_))
+        ));
+        std::fs::remove_file(state_path).unwrap();
+    }
+
+    #[test]
+    fn focus_change_callback() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_on_focus_change(|previous, current, state| {
+            let change = format!("{:?}->{:?}", previous, current);
+            let mut changes = state
+                .get("changes")
+                .map(|x| x.as_list(","))
+                .unwrap_or_default();
+            changes.push(change);
+            state.insert("changes".to_string(), StateValue::List(changes));
+        });
+        let ids: Vec<String> = mp.indexed_elements.iter().map(|x| x.id.clone()).collect();
+
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        mp.handle_key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE));
+        // keys that don't move the focus don't call it
+        mp.handle_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert_eq!(
+            mp.get_state("changes"),
+            Some(&StateValue::List(vec![
+                format!("None->Some({:?})", ids[0]),
+                format!("Some({:?})->None", ids[0]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn combined_weight() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_weight.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let backend = TestBackend::new(12, 14);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        let buffer = terminal.backend().buffer();
+        let cell = |y: u16| (buffer.get(1, y).symbol.clone(), buffer.get(1, y).modifier);
+        assert_eq!(
+            cell(3),
+            ("C".to_string(), Modifier::BOLD | Modifier::ITALIC)
+        );
+        // unknown modifiers add nothing
+        assert_eq!(cell(7), ("U".to_string(), Modifier::empty()));
+        // weight and font-decoration are aliases, "no-bold" wins
+        assert_eq!(cell(11), ("A".to_string(), Modifier::UNDERLINED));
+        Ok(())
+    }
+
+    #[test]
+    fn draw_without_clear() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_no_clear.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_state("showDialog", true);
+        let output = mp.render_plain(30, 30);
+        let lines: Vec<&str> = output.lines().collect();
+        // the body shows through the dialog, its paragraph clears its area
+        assert_eq!(lines[11], "│..........║....║............│");
+        assert_eq!(lines[12], "│..........║.  .║............│");
+    }
+
+    #[test]
+    fn host_loop_integration() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_gauge.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let backend = TestBackend::new(20, 3);
+        let mut terminal = Terminal::new(backend)?;
+        assert!(mp.needs_redraw());
+        terminal.draw(|f| mp.draw_into(f).unwrap())?;
+        assert!(!mp.needs_redraw());
+
+        mp.set_state("download.percent", "50");
+        assert!(mp.needs_redraw());
+        terminal.draw(|f| mp.draw_into(f).unwrap())?;
+        assert!(!mp.needs_redraw());
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+        assert!(text.contains("Down 50%"));
+        Ok(())
+    }
+
+    #[test]
+    fn title_interpolation() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_title_interpolation.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(mp.render_plain(16, 3).starts_with("┌Inbox ()──────┐"));
+        mp.set_state("unread", 3);
+        assert!(mp.render_plain(16, 3).starts_with("┌Inbox (3)─────┐"));
+        mp.set_state("unread", 12);
+        assert!(mp.render_plain(16, 3).starts_with("┌Inbox (12)────┐"));
+    }
+
+    #[test]
+    fn default_focus_highlight() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_focus_highlight.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let backend = TestBackend::new(12, 11);
+        let mut terminal = Terminal::new(backend)?;
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let border = |terminal: &Terminal<TestBackend>, y: u16| {
+            let cell = terminal.backend().buffer().get(1, y);
+            (cell.fg, cell.modifier)
+        };
+
+        mp.handle_key(tab);
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        assert_eq!(border(&terminal, 2), (Color::Yellow, Modifier::BOLD));
+        assert_eq!(border(&terminal, 7), (Color::Reset, Modifier::empty()));
+
+        // a `:focus` rule replaces the default highlight
+        mp.handle_key(tab);
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        assert_eq!(border(&terminal, 2), (Color::Reset, Modifier::empty()));
+        assert_eq!(border(&terminal, 7), (Color::Green, Modifier::empty()));
+        Ok(())
+    }
+
+    #[test]
+    fn focused_borders() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_border_focus.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let unfocused = mp.render_plain(6, 8);
+        assert!(unfocused.starts_with("┌────┐"));
+        // without focus the second container has no borders
+        assert_eq!(unfocused.lines().nth(4), Some(""));
+
+        mp.handle_key(tab);
+        let lines: Vec<String> = mp.render_plain(6, 8).lines().map(String::from).collect();
+        assert_eq!(lines[0], "╔════╗");
+        assert_eq!(lines[3], "╚════╝");
+        assert_eq!(lines[4], "");
+
+        mp.handle_key(tab);
+        let lines: Vec<String> = mp.render_plain(6, 8).lines().map(String::from).collect();
+        assert_eq!(lines[0], "┌────┐");
+        assert_eq!(lines[4], "┌────┐");
+        assert_eq!(lines[7], "└────┘");
+    }
+
+    #[test]
+    fn menu_overlay() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_menu.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.add_action("save_file", |state, _node| {
+            let mut state = state;
+            state.insert("saved".to_string(), true.into());
+            EventResponse::STATE(state)
+        });
+        let key = |code: KeyCode| KeyEvent::new(code, KeyModifiers::NONE);
+        let closed = mp.render_plain(20, 11);
+        assert!(!closed.contains("Open"));
+
+        mp.handle_key(key(KeyCode::Tab));
+        mp.handle_key(key(KeyCode::Enter));
+        let lines = [
+            " ╭────────────────╮",
+            " │      File      │",
+            " ╰────────────────╯",
+            "  Open",
+            "  Save as...",
+            "  Close",
+            " │Body            │",
+        ];
+        // the items are drawn over the body
+        assert!(mp.render_plain(20, 11).contains(&lines.join("\n")));
+        assert_eq!(mp.focused_id().as_deref(), Some("open"));
+
+        // Esc closes the menu
+        mp.handle_key(key(KeyCode::Esc));
+        assert_eq!(mp.render_plain(20, 11), closed);
+        assert!(mp.contexts.is_empty());
+
+        // selecting an item runs its action and closes the menu
+        mp.handle_key(key(KeyCode::Tab));
+        mp.handle_key(key(KeyCode::Enter));
+        mp.render_plain(20, 11);
+        mp.handle_key(key(KeyCode::Down));
+        assert_eq!(mp.focused_id().as_deref(), Some("save"));
+        mp.handle_key(key(KeyCode::Enter));
+        assert_eq!(mp.render_plain(20, 11), closed);
+        assert!(mp.contexts.is_empty());
+        assert_eq!(mp.get_state("saved"), Some(&StateValue::Bool(true)));
+    }
+
+    #[test]
+    fn empty_containers() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_empty_containers.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let lines = ["┌Empty─┐", "│      │", "│      │", "└──────┘"];
+        assert!(mp.render_plain(8, 8).contains(&lines.join("\n")));
+        for (width, height) in [(0, 0), (1, 1), (2, 2), (3, 9)] {
+            mp.render_plain(width, height);
+        }
+    }
+
+    #[test]
+    fn dialog_size() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_dialog_size.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_state("showSmall", true);
+        mp.set_state("showDefault", true);
+        // the dialogs are centered inside the margin of the root layout
+        let areas = mp.compute_layout(102, 42);
+        let area_of = |id: &str| areas.iter().find(|(_, elm_id)| elm_id.eq(id)).unwrap().0;
+        // half the frame wide, 10 rows high, centered
+        assert_eq!(area_of("small"), Rect::new(26, 16, 50, 10));
+        // without size the dialog keeps the default geometry
+        assert_eq!(area_of("default_size"), Rect::new(36, 13, 30, 12));
+    }
+
+    #[test]
+    fn focused_and_active_styles() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_focus_active.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend)?;
+        // the first render selects the first tab
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(mp.focused_id().as_deref(), Some("tab1"));
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        let buffer = terminal.backend().buffer();
+        // the focused and active tab merges both rules
+        let focused = buffer.get(5, 1);
+        assert_eq!(focused.symbol.as_str(), "a");
+        assert_eq!((focused.fg, focused.bg), (Color::Red, Color::Green));
+        let other = buffer.get(14, 1);
+        assert_eq!(other.bg, Color::Reset);
+        // the content of the selected tab gets the active rule
+        assert_eq!(buffer.get(20, 6).bg, Color::Blue);
+        Ok(())
+    }
+
+    #[test]
+    fn select_tab_by_number() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.on_unhandled_key(|key_event, state| {
+            state.insert(
+                "forwarded".to_string(),
+                format!("{:?}", key_event.code).into(),
+            );
+            EventResponse::NOOP
+        });
+        let key = |digit: char| KeyEvent::new(KeyCode::Char(digit), KeyModifiers::NONE);
+        assert!(mp.render_plain(30, 8).contains("Content 1"));
+
+        mp.handle_key(key('2'));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+        assert!(mp.render_plain(30, 8).contains("Content 2"));
+
+        // there is no third tab, the key is forwarded
+        mp.handle_key(key('3'));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+        assert_eq!(mp.get_state("forwarded").unwrap(), "Char('3')");
+    }
+
+    #[test]
+    fn select_tab_by_header_position() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_tabs_disabled.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.on_unhandled_key(|key_event, state| {
+            state.insert(
+                "forwarded".to_string(),
+                format!("{:?}", key_event.code).into(),
+            );
+            EventResponse::NOOP
+        });
+        let key = |digit: char| KeyEvent::new(KeyCode::Char(digit), KeyModifiers::NONE);
+        mp.render_plain(40, 8);
+
+        // the disabled second tab keeps its number
+        mp.handle_key(key('3'));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab3");
+        mp.handle_key(key('2'));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab3");
+        assert_eq!(mp.get_state("forwarded").unwrap(), "Char('2')");
+    }
+
+    #[test]
+    fn terminal_too_small() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_min_size.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert_eq!(mp.render_plain(4, 2), "Term\ninal");
+        assert_eq!(
+            mp.render_plain(19, 4).lines().next(),
+            Some("Terminal too small")
+        );
+        assert!(mp.render_plain(20, 5).contains("Tab 1"));
+        // back to the previous size the layout is computed again
+        mp.render_plain(19, 4);
+        assert!(mp.render_plain(20, 5).contains("Tab 1"));
+
+        // without minimum size the default one applies, bigger frames clip
+        // the elements
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert_eq!(mp.render_plain(4, 2), "Term\ninal");
+        for (width, height) in [(4, 0), (0, 4), (1, 1)] {
+            mp.render_plain(width, height);
+        }
+        assert!(!mp.render_plain(12, 3).contains("Term"));
+    }
+
+    #[test]
+    fn parser_builder() {
+        let asset = |name: &str| match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/{}", exe_path.display(), name),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::builder()
+            .path(&asset("sample_single_block.tml"))
+            .state(HashMap::from([("user".to_string(), "ana".into())]))
+            .tick_rate(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        assert_eq!(mp.get_state("user").unwrap(), "ana");
+
+        let missing = MarkupParser::<TestBackend>::builder().build();
+        assert!(matches!(missing, Err(MarkupError::Load(_))));
+        let missing = MarkupParser::<TestBackend>::builder()
+            .path(&asset("not_found.tml"))
+            .build();
+        assert!(matches!(missing, Err(MarkupError::Load(_))));
+        let malformed = MarkupParser::<TestBackend>::builder()
+            .path(&asset("bad_sample.tml"))
+            .build();
+        assert!(matches!(malformed, Err(MarkupError::Parse { .. })));
+
+        // unknown names only fail in strict mode
+        let builder =
+            || MarkupParser::<TestBackend>::builder().path(&asset("sample_unknown_names.tml"));
+        assert!(builder().build().is_ok());
+        let strict = builder().strict(true).build();
+        assert!(matches!(strict, Err(MarkupError::Strict(_))));
+    }
+
+    #[test]
+    fn external_stylesheet() -> Result<(), Box<dyn Error>> {
+        let asset = |name: &str| match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/{}", exe_path.display(), name),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(asset("sample_stylesheet.tml"), None, None);
+        assert!(mp.warnings().is_empty());
+        let backend = TestBackend::new(10, 10);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        let text = terminal.backend().buffer().get(1, 3).clone();
+        assert_eq!(text.symbol, "T");
+        assert_eq!((text.fg, text.bg), (Color::White, Color::Black));
+        // the inline rules override the stylesheet
+        assert_eq!(terminal.backend().buffer().get(4, 7).fg, Color::Green);
+
+        // a stylesheet loaded later replaces the rules with the same name
+        mp.load_styles(&asset("theme_light.tss"))?;
+        assert!(mp.needs_redraw());
+        terminal.draw(|f| {
+            mp.render_ui(f).unwrap_or(false);
+        })?;
+        let text = terminal.backend().buffer().get(1, 3).clone();
+        assert_eq!((text.fg, text.bg), (Color::Black, Color::White));
+        assert_eq!(terminal.backend().buffer().get(4, 7).fg, Color::Green);
+
+        let missing = mp.load_styles(&asset("missing.tss"));
+        assert!(matches!(missing, Err(MarkupError::Load(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn last_registration_wins() {
+        let asset = |name: &str| match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/{}", exe_path.display(), name),
+            Err(_e) => String::new(),
+        };
+        // the last rule with a name replaces the previous ones
+        let mp = MarkupParser::<TestBackend>::new(asset("sample_duplicated_rules.tml"), None, None);
+        assert_eq!(
+            mp.global_styles.get_rule("p".to_string()).fg,
+            Some(Color::Blue)
+        );
+
+        let mut store = RendererStorage::<TestBackend>::new();
+        store.add_renderer("block", |_node, _area, _state, _f| None);
+        store.add_renderer("block", |_node, area, _state, f| {
+            f.render_widget(Block::default().title("second"), area);
+            Some(())
+        });
+        let mut mp = MarkupParser::new(asset("sample_single_block.tml"), Some(store), None);
+        assert!(mp.render_plain(15, 3).starts_with("second"));
+
+        mp.add_action("greet", |state, _node| {
+            let mut state = state;
+            state.insert("greeting".to_string(), "first".into());
+            EventResponse::STATE(state)
+        });
+        mp.add_action("greet", |state, _node| {
+            let mut state = state;
+            state.insert("greeting".to_string(), "second".into());
+            EventResponse::STATE(state)
+        });
+        mp.dispatch("greet");
+        assert_eq!(mp.get_state("greeting").unwrap(), "second");
+    }
+
+    #[test]
+    fn override_change_tab() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        // tabs only change while the form is valid
+        mp.add_action("__change_tab", |state, node| {
+            let mut state = state;
+            let valid = state.get("form.valid").and_then(StateValue::as_bool);
+            match node {
+                Some(node) if valid.unwrap_or(false) => {
+                    let tabs_id = node.attributes.get("tabs-id").cloned().unwrap_or_default();
+                    state.insert(format!("{}:index", tabs_id), node.id.into());
+                    EventResponse::CLEANFOCUS(state)
+                }
+                _ => EventResponse::NOOP,
+            }
+        });
+        let key = |code: KeyCode| KeyEvent::new(code, KeyModifiers::NONE);
+        mp.render_plain(30, 8);
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab1");
+
+        mp.handle_key(key(KeyCode::Tab));
+        mp.handle_key(key(KeyCode::Tab));
+        mp.handle_key(key(KeyCode::Enter));
+        mp.handle_key(key(KeyCode::Char('2')));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab1");
+
+        mp.set_state("form.valid", true);
+        mp.handle_key(key(KeyCode::Enter));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+    }
+
+    #[test]
+    fn button_labels() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_button_labels.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let screen = mp.render_plain(42, 8);
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[0], "");
+        assert_eq!(lines[1], " ╭──────────╮  ╭──────────╮  ╭──────────╮");
+        assert_eq!(lines[2], " │ Save all │  │Save all …│  │Open it   │");
+        assert_eq!(lines[3], " │    the   │  ╰──────────╯  ╰──────────╯");
+        assert_eq!(lines[4], " │  changes │");
+        assert_eq!(lines[5], " ╰──────────╯");
+    }
+
+    #[test]
+    fn tick_callback() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_gauge.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.set_on_tick(|parser| {
+            let percent = parser
+                .get_state("download.percent")
+                .and_then(StateValue::as_int)
+                .unwrap_or(0);
+            parser.set_state("download.percent", percent + 10);
+        });
+        let backend = TestBackend::new(20, 3);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| mp.draw_into(f).unwrap())?;
+        assert!(!mp.needs_redraw());
+
+        mp.tick();
+        mp.tick();
+        assert!(mp.needs_redraw());
+        assert_eq!(mp.get_state("download.percent").unwrap().as_int(), Some(20));
+        Ok(())
+    }
+
+    #[test]
+    fn contrast_colors() {
+        assert_eq!(contrast_color_of(Color::Rgb(250, 250, 210)), Color::Black);
+        assert_eq!(contrast_color_of(Color::Rgb(20, 20, 60)), Color::White);
+        assert_eq!(contrast_color_of(Color::Indexed(232)), Color::White);
+        assert_eq!(contrast_color_of(Color::Indexed(255)), Color::Black);
+        assert_eq!(contrast_color_of(Color::Indexed(196)), Color::White);
+        assert_eq!(contrast_color_of(Color::Yellow), Color::Black);
+        assert_eq!(contrast_color_of(Color::Blue), Color::White);
+        assert_eq!(contrast_color_of(Color::Reset), Color::Reset);
+        assert_eq!(contrast_color("Blue"), "lightmagenta");
+        assert_eq!(contrast_color("reset"), "");
+    }
+
+    #[test]
+    fn inline_spans() -> Result<(), Box<dyn Error>> {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_inline_spans.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(mp.warnings().is_empty());
+        let screen = mp.render_plain(40, 6);
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[2], " Press q to quit, delete removes");
+        assert_eq!(lines[3], " the item");
+
+        let backend = TestBackend::new(40, 6);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.draw(|f| mp.draw_into(f).unwrap())?;
+        let buffer = terminal.backend().buffer();
+        let cell = |x: u16| (buffer.get(x, 2).symbol.clone(), buffer.get(x, 2).modifier);
+        assert_eq!(cell(1), ("P".to_string(), Modifier::empty()));
+        assert_eq!(cell(7), ("q".to_string(), Modifier::BOLD));
+        assert_eq!(cell(12), ("q".to_string(), Modifier::ITALIC));
+        assert_eq!(cell(18), ("d".to_string(), Modifier::UNDERLINED));
+        assert_eq!(buffer.get(18, 2).fg, Color::Red);
+        assert_eq!(buffer.get(19, 2).fg, Color::Red);
+        assert_eq!(buffer.get(24, 2).fg, Color::Reset);
+        Ok(())
+    }
+
+    #[test]
+    fn scrollbar() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_scrollbar.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let right_edge = |screen: String| -> String {
+            screen
+                .lines()
+                .map(|line| line.chars().last().unwrap_or(' '))
+                .collect()
+        };
+        assert_eq!(right_edge(mp.render_plain(12, 12)), "┐██││┘┐││││┘");
+        mp.set_state("log:scroll", 4);
+        assert_eq!(right_edge(mp.render_plain(12, 12)), "┐││██┘┐││││┘");
+        mp.set_state("log:scroll", 2);
+        assert_eq!(right_edge(mp.render_plain(12, 12)), "┐│██│┘┐││││┘");
+    }
+
+    #[test]
+    fn deferred_actions() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.add_action("do_first", |state, _node| {
+            let mut state = state;
+            state.insert("status".to_string(), "loading".into());
+            EventResponse::THEN(state, "do_work".to_string())
+        });
+        mp.add_action("do_work", |state, _node| {
+            let mut state = state;
+            state.insert("status".to_string(), "done".into());
+            EventResponse::STATE(state)
+        });
+        mp.render_plain(40, 8);
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        mp.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(mp.get_state("status").unwrap().to_string(), "loading");
+        assert!(mp.has_pending_actions());
+
+        assert!(!mp.run_pending_actions());
+        assert_eq!(mp.get_state("status").unwrap().to_string(), "done");
+        assert!(!mp.has_pending_actions());
+    }
+
+    #[test]
+    fn endless_action_chain() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        mp.add_action("poll", |state, _node| {
+            EventResponse::THEN(state, "poll".to_string())
+        });
+        mp.dispatch("poll");
+        let mut rounds = 0;
+        while mp.pending_actions_ready() {
+            assert!(!mp.run_pending_actions());
+            rounds += 1;
+        }
+        // the chain waits for the next tick, the loop reads the keys meanwhile
+        assert_eq!(rounds, 8);
+        assert!(mp.has_pending_actions());
+        mp.tick();
+        assert!(mp.pending_actions_ready());
+    }
+
+    #[test]
+    fn key_modifiers() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let ctrl = KeyModifiers::CONTROL;
+        let shift = KeyModifiers::SHIFT;
+        mp.render_plain(30, 8);
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab1");
+
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, ctrl));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, ctrl));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab1");
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, ctrl | shift));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+        mp.handle_key(KeyEvent::new(KeyCode::Char('1'), ctrl));
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+        assert_eq!(mp.current, -1);
+
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(mp.current, 0);
+        let mut release = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        mp.handle_key(release);
+        assert_eq!(mp.current, 0);
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(mp.current, 1);
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, shift));
+        assert_eq!(mp.current, 0);
+    }
+
+    #[test]
+    fn key_release_filter() {
+        let key = |kind: KeyEventKind| {
+            let mut key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+            key_event.kind = kind;
+            CEvent::Key(key_event)
+        };
+        type Parser = MarkupParser<TestBackend>;
+        assert!(Parser::is_forwarded(&key(KeyEventKind::Press), true));
+        assert!(!Parser::is_forwarded(&key(KeyEventKind::Release), true));
+        assert!(Parser::is_forwarded(&key(KeyEventKind::Repeat), true));
+        assert!(!Parser::is_forwarded(&key(KeyEventKind::Repeat), false));
+        assert!(!Parser::is_forwarded(&CEvent::Resize(10, 10), true));
+
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::builder()
+            .path(&filepath)
+            .key_repeat(false)
+            .build()
+            .unwrap();
+        mp.add_action("do_first", |state, _node| {
+            let mut state = state;
+            let count = state.get("count").and_then(StateValue::as_int).unwrap_or(0);
+            state.insert("count".to_string(), (count + 1).into());
+            EventResponse::STATE(state)
+        });
+        mp.render_plain(40, 8);
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        for kind in [
+            KeyEventKind::Press,
+            KeyEventKind::Repeat,
+            KeyEventKind::Release,
+        ] {
+            let mut enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+            enter.kind = kind;
+            mp.handle_key(enter);
+        }
+        assert_eq!(mp.get_state("count").unwrap().as_int(), Some(1));
+    }
+
+    #[test]
+    fn element_navigation() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_tabs.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let ids = |elements: Vec<MarkupElement>| -> Vec<String> {
+            elements.into_iter().map(|elm| elm.id).collect()
+        };
+        let paragraph = mp.find_element("prg-1").unwrap();
+        assert_eq!(paragraph.parent().unwrap().id, "ctt-1");
+        assert_eq!(
+            ids(paragraph.ancestors()),
+            vec!["ctt-1", "t-body", "tabs-cmp", "blk1", "root"]
+        );
+        assert!(paragraph.siblings().is_empty());
+
+        let tab = mp.find_element("tab1").unwrap();
+        assert_eq!(ids(tab.siblings()), vec!["tab2"]);
+
+        let root = mp.find_element("root").unwrap();
+        assert!(root.parent().is_none());
+        assert!(root.ancestors().is_empty());
+        let content = root.child_by_id("prg-2").unwrap();
+        assert_eq!(content.text.unwrap(), "Content 2");
+        assert!(root.child_by_id("missing").is_none());
+    }
+
+    #[test]
+    fn siblings_with_repeated_ids() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_repeated_ids.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let texts = |elements: Vec<MarkupElement>| -> Vec<String> {
+            elements
+                .into_iter()
+                .map(|elm| elm.text.unwrap_or_default())
+                .collect()
+        };
+        let mut first = mp.find_element("line").unwrap();
+        assert_eq!(texts(first.siblings()), vec!["Second", "Third"]);
+        // a renamed copy is still the same element
+        first.id = "renamed".to_string();
+        assert_eq!(texts(first.siblings()), vec!["Second", "Third"]);
+
+        // the elements of the tree are told apart by identity
+        let block = mp.find_element("blk").unwrap();
+        let second = block.children[1].as_ref().borrow();
+        assert_eq!(texts(second.siblings()), vec!["First", "Third"]);
+    }
+
+    #[test]
+    fn button_height() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_button_height.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let screen = mp.render_plain(36, 18);
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[3], " │   Go   │  │        │  ╰────────╯");
+        assert_eq!(lines[5], " ╰────────╯  │  Half  │");
+        assert_eq!(lines[8], "             ╰────────╯");
+        assert_eq!(lines[9], "");
+    }
+
+    #[test]
+    fn no_widgets_message() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_only_styles.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(!mp.failed);
+        let screen = mp.render_plain(60, 5);
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(
+            lines[2],
+            "     Layout produced no widgets, check your root element"
+        );
+    }
+
+    #[test]
+    fn direction_shorthand() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_direction.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert_eq!(
+            mp.warnings(),
+            vec!["Unknown direction \"verical\" in <layout>".to_string()]
+        );
+        let layout = mp.compute_layout(20, 6);
+        let area = |id: &str| layout.iter().find(|(_, name)| name.eq(id)).unwrap().0;
+        // the root is vertical, the layout without direction horizontal
+        assert_eq!(area("left"), Rect::new(1, 1, 9, 1));
+        assert_eq!(area("right"), Rect::new(10, 1, 9, 1));
+        assert_eq!(area("bottom"), Rect::new(1, 4, 18, 1));
+    }
+
+    #[test]
+    fn comments_keep_text() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_comments.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(!mp.failed);
+        assert!(mp.warnings().is_empty());
+        let text = |id: &str| mp.find_element(id).and_then(|elm| elm.text);
+        assert_eq!(text("title").unwrap(), "Title");
+        assert_eq!(text("body_text").unwrap(), "Body");
+        assert_eq!(text("footer").unwrap(), "Footer");
+        assert_eq!(text("body"), None);
+    }
+
+    #[test]
+    fn spanned_cells() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_span.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let layout = mp.compute_layout(40, 3);
+        let area = |id: &str| {
+            layout
+                .iter()
+                .find(|(_, name)| name.eq(id))
+                .map(|(area, _)| *area)
+        };
+        assert_eq!(area("wide"), Some(Rect::new(0, 0, 20, 3)));
+        assert_eq!(area("one"), Some(Rect::new(20, 0, 10, 3)));
+        assert_eq!(area("clamped"), Some(Rect::new(30, 0, 10, 3)));
+        assert_eq!(area("extra"), None);
+        assert_eq!(
+            mp.warnings(),
+            vec!["Layout #root has more children than constraints".to_string()]
+        );
+    }
+
+    #[test]
+    fn render_errors() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/bad_sample.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        // a failed parser returns its error instead of panicking
+        let res = mp.ui_loop(TestBackend::new(20, 5), |_key, _state| EventResponse::NOOP);
+        let error = res.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<MarkupError>(),
+            Some(MarkupError::Parse { .. })
+        ));
+        let mut terminal = Terminal::new(TestBackend::new(20, 5)).unwrap();
+        let mut message = String::new();
+        terminal
+            .draw(|frame| {
+                message = mp.draw_into(frame).err().unwrap();
+            })
+            .unwrap();
+        assert!(!message.is_empty());
+        assert_eq!(MarkupError::Render(message.clone()).to_string(), message);
+    }
+
+    // it switches the real terminal to raw mode and the alternate screen,
+    // run it from a terminal with `cargo test -- --ignored terminal_guard`
+    #[test]
+    #[ignore = "needs an interactive terminal"]
+    fn terminal_guard() {
+        let res = std::panic::catch_unwind(|| {
+            let _guard = TerminalGuard::new(true).unwrap();
+            assert!(is_raw_mode_enabled().unwrap());
+            panic!("the loop failed");
+        });
+        assert!(res.is_err());
+        // the guard was dropped while panicking
+        assert!(!is_raw_mode_enabled().unwrap());
+    }
+
+    #[test]
+    fn alternate_screen_option() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        assert!(mp.uses_alternate_screen());
+        mp.set_alternate_screen(false);
+        assert!(!mp.uses_alternate_screen());
+        let mp = MarkupParser::<TestBackend>::builder()
+            .path(&filepath)
+            .alternate_screen(false)
+            .build()
+            .unwrap();
+        assert!(!mp.uses_alternate_screen());
+    }
+
+    #[test]
+    fn initial_state_from_markup() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_initial_state.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath.clone(), None, None);
+        let output = mp.render_plain(60, 40);
+        assert_eq!(mp.get_state("tabs-cmp:index").unwrap(), "tab2");
+        assert_eq!(mp.get_state("showDialog"), Some(&StateValue::from(true)));
+        assert!(output.contains("Welcome"));
+        assert_eq!(mp.focused_id().as_deref(), Some("dlg_btn_Ok"));
+        // once closed the dialog doesn't open again
+        mp.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let output = mp.render_plain(60, 40);
+        assert!(!output.contains("Welcome"));
+        assert!(output.contains("Content 2"));
+
+        // the initial state wins over the markup defaults
+        let mut state: HashMap<String, StateValue> = HashMap::new();
+        state.insert("tabs-cmp:index".to_string(), "tab1".into());
+        state.insert("showDialog".to_string(), false.into());
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, Some(state));
+        let output = mp.render_plain(60, 40);
+        assert!(output.contains("Content 1"));
+        assert!(!output.contains("Welcome"));
+    }
+
+    #[test]
+    fn open_dialog_without_show() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_open_dialog_no_show.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        mp.render_plain(40, 10);
+        assert!(mp.get_state("").is_none());
+        assert_eq!(
+            mp.warnings(),
+            vec!["Dialog #dlg has open=\"true\" but no show attribute".to_string()]
+        );
+    }
+
+    #[test]
+    fn render_progress() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_progress.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        // the unknown progress moves on every tick
+        mp.set_state("job", "loading");
+        assert_eq!(mp.render_plain(20, 3), "\nJob ████\n");
+        mp.tick();
+        assert_eq!(mp.render_plain(20, 3), "\nJob  ████\n");
+        // and bounces back at the end
+        for _ in 0..20 {
+            mp.tick();
+        }
+        assert_eq!(mp.render_plain(20, 3), "\nJob    ████\n");
+        // a known progress is drawn as a gauge
+        mp.set_state("job", 40);
+        assert_eq!(mp.render_plain(20, 3).trim(), "Job 40%");
+    }
+
+    #[test]
+    fn fraction_constraints() {
+        type Parser = MarkupParser<TestBackend>;
+        let constraints = |values: &[&str], available: u16| {
+            let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            Parser::get_fraction_constraints(&values, available)
+        };
+        assert_eq!(
+            constraints(&["10", "2fr", "1fr", "20%"], 100),
+            [
+                Constraint::Length(10),
+                Constraint::Length(47),
+                Constraint::Length(23),
+                Constraint::Percentage(20)
+            ]
+        );
+        // the rounding doesn't lose cells
+        assert_eq!(
+            constraints(&["fr", "fr", "fr"], 10),
+            [
+                Constraint::Length(3),
+                Constraint::Length(4),
+                Constraint::Length(3)
+            ]
+        );
+        // without space left the fractions are empty
+        assert_eq!(
+            constraints(&["30", "1fr"], 20),
+            [Constraint::Length(30), Constraint::Length(0)]
+        );
+
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_fractions.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mp = Parser::new(filepath, None, None);
+        let layout = mp.compute_layout(100, 12);
+        let area = |id: &str| layout.iter().find(|(_, name)| name.eq(id)).unwrap().0;
+        assert_eq!(area("double"), Rect::new(10, 0, 47, 12));
+        assert_eq!(area("single"), Rect::new(57, 0, 23, 12));
+        // the cells of a layout take fractions too
+        assert_eq!(area("middle"), Rect::new(81, 3, 18, 2));
+        assert_eq!(area("bottom"), Rect::new(81, 5, 18, 6));
+    }
+
+    #[test]
+    fn structured_test_check() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_duplicated_rules.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        let (elements, styles) = mp.test_check(TestBackend::new(20, 6), false).unwrap();
+        assert_eq!(
+            elements,
+            vec![(Rect::new(1, 2, 18, 3), "p".to_string(), "text".to_string())]
+        );
+        assert_eq!(styles.get_rule("p".to_string()).fg, Some(Color::Blue));
+    }
+
+    #[test]
+    fn space_activates_buttons() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_buttons.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        mp.add_action("do_first", |state, _node| {
+            let mut state = state;
+            let count = state.get("count").and_then(StateValue::as_int).unwrap_or(0);
+            state.insert("count".to_string(), (count + 1).into());
+            EventResponse::STATE(state)
+        });
+        mp.on_unhandled_key(|key_event, state| {
+            if key_event.code == KeyCode::Char(' ') {
+                state.insert("spaces".to_string(), true.into());
+            }
+            EventResponse::NOOP
+        });
+        let space = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        mp.render_plain(40, 8);
+        // without focus Space is a regular key
+        mp.handle_key(space);
+        assert!(mp.get_state("count").is_none());
+        assert_eq!(mp.get_state("spaces"), Some(&StateValue::from(true)));
+
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(mp.focused_name().as_deref(), Some("button"));
+        mp.handle_key(space);
+        assert_eq!(mp.get_state("count").unwrap(), "1");
+        mp.handle_key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::CONTROL));
+        assert_eq!(mp.get_state("count").unwrap(), "1");
+    }
+
+    #[test]
+    fn render_in_area() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_dialog_size.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        mp.set_state("showSmall", true);
+        let mut terminal = Terminal::new(TestBackend::new(60, 30)).unwrap();
+        terminal
+            .draw(|frame| {
+                mp.render_ui_in(frame, Rect::new(20, 10, 40, 20)).unwrap();
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let lines: Vec<String> = (0..30)
+            .map(|y| (0..60).map(|x| buffer.get(x, y).symbol.clone()).collect())
+            .collect();
+        // nothing is drawn out of the area
+        assert!(lines[..10].iter().all(|line| line.trim().is_empty()));
+        assert_eq!(lines[10].find('╭'), Some(20));
+        // the dialog is centered in the area, not in the frame
+        assert_eq!(
+            lines[15].trim_end(),
+            format!("{}╔{}╗", " ".repeat(30), "═".repeat(17))
+        );
+        assert_eq!(
+            lines[24].trim_end(),
+            format!("{}╚{}╝", " ".repeat(30), "═".repeat(17))
+        );
+    }
+
+    #[test]
+    fn container_children_cells() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!(
+                "{}/tests/assets/sample_container_cells.tml",
+                exe_path.display()
+            ),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        // every child gets a cell, even when they don't fit in the container
+        for width in [40, 6, 2, 0] {
+            mp.render_plain(width, 5);
+            let layout = mp.compute_layout(width, 5);
+            for id in ["btn_open", "btn_save", "btn_quit"] {
+                assert!(layout.iter().any(|(_, layout_id)| layout_id == id));
+            }
+        }
+        assert!(mp.warnings().is_empty());
+    }
+
+    #[test]
+    fn ellipsis_overflow() {
+        assert_eq!(truncate_with_ellipsis("notes.txt", 9), "notes.txt");
+        assert_eq!(truncate_with_ellipsis("notes.txt", 6), "notes…");
+        assert_eq!(truncate_with_ellipsis("notes.txt", 1), "…");
+        assert_eq!(truncate_with_ellipsis("notes.txt", 0), "");
+        // wide characters take two columns, the gap they leave stays empty
+        assert_eq!(truncate_with_ellipsis("日本語テキスト", 6), "日本…");
+        assert_eq!(truncate_with_ellipsis("日本語テキスト", 7), "日本語…");
+        assert_eq!(truncate_with_ellipsis("héllo wörld", 8), "héllo w…");
+
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_ellipsis.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        let output = mp.render_plain(20, 11);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "┌/home/user/projec…┐");
+        // without overflow the title is clipped
+        assert_eq!(lines[3], "┌/home/user/project┐");
+        assert_eq!(lines[7], "│Download everythi…│");
+    }
+
+    #[test]
+    fn block_root() {
+        let filepath = match current_dir() {
+            Ok(exe_path) => format!("{}/tests/assets/sample_block_root.tml", exe_path.display()),
+            Err(_e) => String::new(),
+        };
+        let mut mp = MarkupParser::<TestBackend>::new(filepath, None, None);
+        assert!(!mp.failed);
+        // the root block is a bordered panel taking the whole screen
+        let layout = mp.compute_layout(30, 12);
+        assert_eq!(layout[0], (Rect::new(0, 0, 30, 12), "panel".to_string()));
+        let output = mp.render_plain(30, 12);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], format!("┌Panel{}┐", "─".repeat(23)));
+        assert_eq!(lines[2], "││     Ok     │              │");
+        assert_eq!(lines[11], format!("└{}┘", "─".repeat(28)));
+        mp.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(mp.focused_id().as_deref(), Some("btn_ok"));
+
+        // its dialogs are drawn over the other children
+        mp.set_state("showDialog", true);
+        let output = mp.render_plain(30, 12);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[3], "│╰────────╔═══════╗          │");
+        assert_eq!(mp.focused_id().as_deref(), Some("dlg_btn_Close"));
+    }
 }